@@ -37,7 +37,52 @@ find "$IN_DIR" -name \*.csv > "$OUT_DIR/name_csv.txt"
 find "$IN_DIR" -name \*.csv -o -name \*.mp3 > "$OUT_DIR/name_csv_mp3.txt"
 find "$IN_DIR/a" "$IN_DIR/d" -name \*.txt > "$OUT_DIR/name_txt_path_a_d.txt"
 
-find "$IN_DIR" -name a* > "$OUT_DIR/name_a.txt"
-find "$IN_DIR" -type f -name a* > "$OUT_DIR/type_f_name_a.txt"
-find "$IN_DIR" -type d -name a* > "$OUT_DIR/type_d_name_a.txt"
+find "$IN_DIR" -name 'a*' > "$OUT_DIR/name_a.txt"
+find "$IN_DIR" -type f -name 'a*' > "$OUT_DIR/type_f_name_a.txt"
+find "$IN_DIR" -type d -name 'a*' > "$OUT_DIR/type_d_name_a.txt"
+find "$IN_DIR" -name 'a' > "$OUT_DIR/name_exact_a.txt"
+find "$IN_DIR" -name '?.txt' > "$OUT_DIR/name_question.txt"
+find "$IN_DIR" -name '[b-d].*' > "$OUT_DIR/name_class.txt"
+find "$IN_DIR" -iname '*.CSV' > "$OUT_DIR/iname_csv.txt"
+find "$IN_DIR" -regex '.*/[bg]\.csv' > "$OUT_DIR/regex_csv.txt"
+find "$IN_DIR" -regex 'csv' > "$OUT_DIR/regex_unanchored.txt"
+find "$IN_DIR" -iregex '.*/D/.*\.TXT' > "$OUT_DIR/iregex_d_txt.txt"
 
+
+find "$IN_DIR" -maxdepth 0 > "$OUT_DIR/maxdepth_0.txt"
+find "$IN_DIR" -maxdepth 1 > "$OUT_DIR/maxdepth_1.txt"
+find "$IN_DIR" -maxdepth 2 -type f > "$OUT_DIR/type_f_maxdepth_2.txt"
+find "$IN_DIR" -mindepth 2 > "$OUT_DIR/mindepth_2.txt"
+find "$IN_DIR" -mindepth 4 > "$OUT_DIR/mindepth_4.txt"
+find "$IN_DIR/a" "$IN_DIR/d" -mindepth 1 -maxdepth 1 > "$OUT_DIR/mindepth_1_maxdepth_1_path_a_d.txt"
+find "$IN_DIR" -mindepth 3 -maxdepth 2 > "$OUT_DIR/mindepth_3_maxdepth_2.txt"
+
+find "$IN_DIR" -type f -name '*.csv' -o -type d -name 'a*' > "$OUT_DIR/expr_or_and.txt"
+find "$IN_DIR" \( -name 'a*' -or -name 'b*' \) -and -type f > "$OUT_DIR/expr_parens.txt"
+find "$IN_DIR" ! -type d -not -name '*.csv' > "$OUT_DIR/expr_not.txt"
+find "$IN_DIR" -name 'a*' -print -o -type l > "$OUT_DIR/expr_print.txt"
+
+find "$IN_DIR" -type f -exec echo found {} \; > "$OUT_DIR/exec_echo.txt"
+find "$IN_DIR" -name '*.csv' -execdir echo {} + > "$OUT_DIR/execdir_batch.txt"
+find "$IN_DIR" -type d -exec false \; -o -type d -print > "$OUT_DIR/exec_false_or.txt"
+
+find "$IN_DIR" -print0 > "$OUT_DIR/print0.txt"
+find "$IN_DIR" -printf '%y %d %m %M %-3s|%h|%f|%l|\t%p\n' > "$OUT_DIR/printf.txt"
+find "$IN_DIR" -type f -printf '%%\101\t%f\0' > "$OUT_DIR/printf_escapes.txt"
+
+find "$IN_DIR" -size 2c > "$OUT_DIR/size_2c.txt"
+find "$IN_DIR" -size 1k > "$OUT_DIR/size_1k.txt"
+find "$IN_DIR" -size +1k -o -size -1 > "$OUT_DIR/size_more_1k_less_1.txt"
+find "$IN_DIR" -perm -o+w > "$OUT_DIR/perm_all_o_w.txt"
+find "$IN_DIR" -perm /u+x > "$OUT_DIR/perm_any_u_x.txt"
+find "$IN_DIR" -type f -perm 664 > "$OUT_DIR/perm_664.txt"
+find "$IN_DIR" -mtime -36500 > "$OUT_DIR/mtime_less_36500.txt"
+
+find "$IN_DIR" -name a -prune -o -print > "$OUT_DIR/prune_a.txt"
+find "$IN_DIR" -type d -name b -prune -o -type f -print > "$OUT_DIR/prune_b_type_f.txt"
+find "$IN_DIR" -name '*.csv' -prune > "$OUT_DIR/prune_csv.txt"
+
+find -L "$IN_DIR" > "$OUT_DIR/follow_l.txt"
+find -L "$IN_DIR" -type f > "$OUT_DIR/follow_l_type_f.txt"
+find -L "$IN_DIR" -type l > "$OUT_DIR/follow_l_type_l.txt"
+find -H "$IN_DIR" -type l > "$OUT_DIR/follow_h_type_l.txt"
@@ -1,9 +1,47 @@
-use std::{error::Error, path::PathBuf};
+use std::{
+    borrow::Cow,
+    cell::{Cell, OnceCell},
+    env,
+    error::Error,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, Write},
+    iter, mem,
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use clap::{arg, Arg, ArgAction, Command};
-use regex::Regex;
+use chrono::{DateTime, Local};
+use clap::{Arg, ArgAction, Command};
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use regex::{Regex, RegexBuilder};
 use walkdir::{DirEntry, WalkDir};
 
+/// which symlinks the walk follows
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Follow {
+    /// -P
+    #[default]
+    Never,
+    /// -L
+    Always,
+    /// -H: only the paths to walk
+    Paths,
+}
+
+impl Follow {
+    /// whether a link at `depth` is followed
+    fn at(&self, depth: usize) -> bool {
+        match self {
+            Follow::Never => false,
+            Follow::Always => true,
+            Follow::Paths => 0 == depth,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FindType {
     Dir,
@@ -11,20 +49,1298 @@ pub enum FindType {
     Link,
 }
 
+/// a test on the name of an entry
+#[derive(Debug)]
+pub enum NameMatch {
+    /// -name/-iname: a shell glob, matched against the whole basename
+    Name(GlobMatcher),
+    /// -regex/-iregex: a regex, matched against the whole path
+    Path(Regex),
+}
+
+impl NameMatch {
+    fn glob(pattern: &str, case_insensitive: bool) -> MyResult<Self> {
+        let pattern = escape_braces(pattern);
+        let glob = GlobBuilder::new(&pattern)
+            .case_insensitive(case_insensitive)
+            .literal_separator(true)
+            .build()?;
+        Ok(NameMatch::Name(glob.compile_matcher()))
+    }
+
+    fn regex(pattern: &str, case_insensitive: bool) -> MyResult<Self> {
+        let regex = RegexBuilder::new(&format!("^(?:{})$", pattern))
+            .case_insensitive(case_insensitive)
+            .build()?;
+        Ok(NameMatch::Path(regex))
+    }
+
+    fn is_match(&self, entry: &Entry) -> bool {
+        match self {
+            NameMatch::Name(glob) => glob.is_match(entry.file_name()),
+            NameMatch::Path(regex) => regex.is_match(&entry.path().to_string_lossy()),
+        }
+    }
+}
+
+/// `pattern` with its braces made literal: globset reads "{a,b}" as a choice, but to find's
+/// fnmatch they're plain chars. braces in a bracket expression are left as they are
+fn escape_braces(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                escaped.push(c);
+                escaped.extend(chars.next());
+            }
+            '[' if !in_class => {
+                in_class = true;
+                escaped.push(c);
+                // a ']' right after the opening one (or its negation) is part of the class
+                if let Some(&negation) = chars.peek().filter(|&&c| '!' == c || '^' == c) {
+                    escaped.push(negation);
+                    chars.next();
+                }
+                if let Some(&bracket) = chars.peek().filter(|&&c| ']' == c) {
+                    escaped.push(bracket);
+                    chars.next();
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                escaped.push(c);
+            }
+            '{' | '}' if !in_class => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// a numeric argument, which is more than N with "+N", less with "-N", and exactly N otherwise
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    Less(u64),
+    Equal(u64),
+    Greater(u64),
+}
+
+impl Comparison {
+    fn parse(arg: &str) -> Option<Self> {
+        let (make, n): (fn(u64) -> Self, _) = match arg.as_bytes().first() {
+            Some(b'+') => (Comparison::Greater, &arg[1..]),
+            Some(b'-') => (Comparison::Less, &arg[1..]),
+            _ => (Comparison::Equal, arg),
+        };
+        if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        n.parse().ok().map(make)
+    }
+
+    fn is_match(&self, value: i64) -> bool {
+        match *self {
+            Comparison::Less(n) => value < n as i64,
+            Comparison::Equal(n) => value == n as i64,
+            Comparison::Greater(n) => value > n as i64,
+        }
+    }
+
+    /// like `is_match` for a value that isn't whole, where N takes what's more than N - 1
+    /// up to N
+    fn is_within(&self, value: f64) -> bool {
+        match *self {
+            Comparison::Less(n) => value < n as f64,
+            Comparison::Equal(n) => n as f64 - 1.0 < value && value <= n as f64,
+            Comparison::Greater(n) => value > n as f64,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeField {
+    Accessed,
+    Changed,
+    Modified,
+}
+
+/// how -perm compares the permission bits
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PermMatch {
+    /// -perm MODE: exactly these
+    Exact,
+    /// -perm -MODE: all of these, and maybe more
+    All,
+    /// -perm /MODE: any of these
+    Any,
+}
+
+/// a test on the metadata of an entry, which is only read for the entries these get to
+#[derive(Debug)]
+pub enum MetaTest {
+    /// -size: the size in `unit`s, rounded up
+    Size(Comparison, u64),
+    /// -mtime and such: how many `unit`s of seconds ago `field` was, as of `now`; find drops
+    /// what's left of a day, but not of a minute
+    Time {
+        field: TimeField,
+        comparison: Comparison,
+        unit: u64,
+        now: SystemTime,
+    },
+    /// -newer: modified after this
+    Newer(SystemTime),
+    Empty,
+    Perm(PermMatch, u32),
+    /// -uid and -user
+    Uid(Comparison),
+    /// -gid and -group
+    Gid(Comparison),
+}
+
+impl MetaTest {
+    fn is_match(&self, entry: &Entry, metadata: &fs::Metadata) -> bool {
+        match self {
+            MetaTest::Size(comparison, unit) => {
+                comparison.is_match(metadata.len().div_ceil(*unit) as i64)
+            }
+            MetaTest::Time {
+                field,
+                comparison,
+                unit,
+                now,
+            } => {
+                let age = match now.duration_since(time(metadata, *field)) {
+                    Ok(age) => age.as_secs_f64(),
+                    Err(e) => -e.duration().as_secs_f64(),
+                };
+                let age = age / *unit as f64;
+                if *unit < 24 * 60 * 60 {
+                    comparison.is_within(age)
+                } else {
+                    comparison.is_match(age.floor() as i64)
+                }
+            }
+            MetaTest::Newer(reference) => time(metadata, TimeField::Modified) > *reference,
+            MetaTest::Empty => {
+                if metadata.is_dir() {
+                    fs::read_dir(entry.path()).is_ok_and(|mut entries| entries.next().is_none())
+                } else {
+                    metadata.is_file() && 0 == metadata.len()
+                }
+            }
+            MetaTest::Perm(how, bits) => {
+                let mode = mode(metadata) & 0o7777;
+                match how {
+                    PermMatch::Exact => mode == *bits,
+                    PermMatch::All => mode & bits == *bits,
+                    PermMatch::Any => 0 == *bits || 0 != mode & bits,
+                }
+            }
+            MetaTest::Uid(comparison) => comparison.is_match(owner(metadata).0 as i64),
+            MetaTest::Gid(comparison) => comparison.is_match(owner(metadata).1 as i64),
+        }
+    }
+}
+
+/// the bits of a mode given as octal, or symbolically as chmod takes it, like "u+w,go=r"
+fn parse_mode(mode: &str) -> Option<u32> {
+    if !mode.is_empty() && mode.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(mode, 8)
+            .ok()
+            .filter(|&bits| bits <= 0o7777);
+    }
+
+    let mut bits = 0;
+    for clause in mode.split(',') {
+        let (who, ops) = clause.split_at(clause.find(['+', '-', '='])?);
+        let mut mask = 0;
+        for c in who.chars() {
+            mask |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return None,
+            };
+        }
+        if who.is_empty() {
+            mask = 0o7777;
+        }
+
+        let mut chars = ops.chars().peekable();
+        while let Some(op) = chars.next() {
+            let mut perm = 0;
+            while let Some(c) = chars.next_if(|c| !matches!(c, '+' | '-' | '=')) {
+                perm |= match c {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' | 'X' => 0o111,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    _ => return None,
+                };
+            }
+            let perm = perm & mask;
+            match op {
+                '+' => bits |= perm,
+                '-' => bits &= !perm,
+                _ => bits = (bits & !mask) | perm,
+            }
+        }
+    }
+    Some(bits)
+}
+
+/// when `field` of a file was last touched
+#[cfg(unix)]
+fn time(metadata: &fs::Metadata, field: TimeField) -> SystemTime {
+    use std::os::unix::fs::MetadataExt;
+
+    let (secs, nanos) = match field {
+        TimeField::Accessed => (metadata.atime(), metadata.atime_nsec()),
+        TimeField::Changed => (metadata.ctime(), metadata.ctime_nsec()),
+        TimeField::Modified => (metadata.mtime(), metadata.mtime_nsec()),
+    };
+    let since = std::time::Duration::new(secs.unsigned_abs(), nanos as u32);
+    if secs < 0 {
+        UNIX_EPOCH - since
+    } else {
+        UNIX_EPOCH + since
+    }
+}
+
+#[cfg(not(unix))]
+fn time(metadata: &fs::Metadata, field: TimeField) -> SystemTime {
+    match field {
+        TimeField::Accessed => metadata.accessed(),
+        // there's no change time, and creation is what's closest
+        TimeField::Changed => metadata.created(),
+        TimeField::Modified => metadata.modified(),
+    }
+    .unwrap_or(UNIX_EPOCH)
+}
+
+/// the user and group ids of the owner
+#[cfg(unix)]
+fn owner(metadata: &fs::Metadata) -> (u32, u32) {
+    use std::os::unix::fs::MetadataExt;
+
+    (metadata.uid(), metadata.gid())
+}
+
+#[cfg(not(unix))]
+fn owner(_: &fs::Metadata) -> (u32, u32) {
+    (0, 0)
+}
+
+/// an entry of the walk, along with its metadata once something asked for it
+struct Entry<'a> {
+    path: &'a Path,
+    depth: usize,
+    /// the type of what the entry points to when it's a link that's followed
+    file_type: fs::FileType,
+    follow: bool,
+    metadata: OnceCell<Option<fs::Metadata>>,
+    /// whether -prune said not to go into the entry
+    pruned: Cell<bool>,
+    /// whether something about the entry couldn't be read, which fails the run
+    failed: Cell<bool>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(dir_entry: &'a DirEntry, follow: bool) -> Self {
+        let mut file_type = dir_entry.file_type();
+        // walkdir goes into the paths to walk that are links, but has them as links
+        if follow && file_type.is_symlink() {
+            if let Ok(metadata) = fs::metadata(dir_entry.path()) {
+                file_type = metadata.file_type();
+            }
+        }
+        Entry {
+            path: dir_entry.path(),
+            depth: dir_entry.depth(),
+            file_type,
+            follow,
+            metadata: OnceCell::new(),
+            pruned: Cell::new(false),
+            failed: Cell::new(false),
+        }
+    }
+
+    /// a link that walkdir couldn't follow since it points nowhere, which find still has as
+    /// an entry
+    fn broken_link(error: &'a walkdir::Error) -> Option<Self> {
+        let path = error.path()?;
+        if error.io_error()?.kind() != io::ErrorKind::NotFound {
+            return None;
+        }
+        let metadata = fs::symlink_metadata(path).ok()?;
+        if !metadata.file_type().is_symlink() {
+            return None;
+        }
+        Some(Entry {
+            path,
+            depth: error.depth(),
+            file_type: metadata.file_type(),
+            follow: true,
+            metadata: OnceCell::from(Some(metadata)),
+            pruned: Cell::new(false),
+            failed: Cell::new(false),
+        })
+    }
+
+    fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// the last part of the path, or the path itself when it has none, like "/"
+    fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn file_type(&self) -> fs::FileType {
+        self.file_type
+    }
+
+    /// the entry's metadata, which is read at most once; None when it can't be
+    fn metadata(&self) -> Option<&fs::Metadata> {
+        self.metadata
+            .get_or_init(|| {
+                let metadata = if self.follow {
+                    fs::metadata(self.path)
+                } else {
+                    fs::symlink_metadata(self.path)
+                };
+                match metadata {
+                    Ok(metadata) => Some(metadata),
+                    Err(e) => {
+                        eprintln!("{}: {}", self.path.display(), e);
+                        self.failed.set(true);
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+}
+
+/// a find expression, evaluated for every entry the walk comes across
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Type(FindType),
+    Name(NameMatch),
+    Meta(MetaTest),
+    Print,
+    /// -prune: true, and the walk doesn't go into the directory
+    Prune,
+    /// -print0: the path followed by a NUL instead of a newline
+    Print0,
+    Printf(Format),
+    Exec(Exec),
+    /// what the global options (-maxdepth and such) and an empty expression evaluate to
+    True,
+}
+
+impl Expr {
+    /// whether `entry` passes; the right side of -a and -o is only evaluated when it's needed,
+    /// so actions on it only run then
+    fn eval(&mut self, entry: &Entry, out: &mut Vec<u8>) -> MyResult<bool> {
+        Ok(match self {
+            Expr::And(left, right) => left.eval(entry, out)? && right.eval(entry, out)?,
+            Expr::Or(left, right) => left.eval(entry, out)? || right.eval(entry, out)?,
+            Expr::Not(expr) => !expr.eval(entry, out)?,
+            Expr::Type(FindType::Link) => entry.file_type().is_symlink(),
+            Expr::Type(FindType::Dir) => entry.file_type().is_dir(),
+            Expr::Type(FindType::File) => entry.file_type().is_file(),
+            Expr::Name(name) => name.is_match(entry),
+            Expr::Meta(test) => entry
+                .metadata()
+                .is_some_and(|metadata| test.is_match(entry, metadata)),
+            Expr::Print => {
+                out.extend_from_slice(&bytes(entry.path().as_os_str()));
+                out.push(b'\n');
+                true
+            }
+            Expr::Prune => {
+                entry.pruned.set(true);
+                true
+            }
+            Expr::Print0 => {
+                out.extend_from_slice(&bytes(entry.path().as_os_str()));
+                out.push(b'\0');
+                true
+            }
+            Expr::Printf(format) => {
+                format.render(entry, out);
+                true
+            }
+            Expr::Exec(exec) => {
+                // whatever was printed before goes out before the command's output
+                flush(out)?;
+                exec.eval(entry)
+            }
+            Expr::True => true,
+        })
+    }
+
+    /// runs what's left in the batches of -exec ... +, and tells whether all the commands of
+    /// those succeeded
+    fn finish(&mut self) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                // both sides, even if the left one failed
+                let left = left.finish();
+                right.finish() && left
+            }
+            Expr::Not(expr) => expr.finish(),
+            Expr::Exec(exec) => exec.finish(),
+            Expr::Type(_)
+            | Expr::Name(_)
+            | Expr::Meta(_)
+            | Expr::Print
+            | Expr::Prune
+            | Expr::Print0
+            | Expr::Printf(_)
+            | Expr::True => true,
+        }
+    }
+
+    fn has_action(&self) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.has_action() || right.has_action()
+            }
+            Expr::Not(expr) => expr.has_action(),
+            Expr::Print | Expr::Print0 | Expr::Printf(_) | Expr::Exec(_) => true,
+            // as with find, -prune still has the matches printed
+            Expr::Type(_) | Expr::Name(_) | Expr::Meta(_) | Expr::Prune | Expr::True => false,
+        }
+    }
+}
+
+/// the format of -printf, as text and the directives to fill in between
+#[derive(Debug)]
+pub struct Format(Vec<Piece>);
+
+#[derive(Debug)]
+enum Piece {
+    Text(Vec<u8>),
+    /// a directive padded to `width`, on the left unless it's `left` aligned, as with %-10p
+    Field {
+        directive: Directive,
+        left: bool,
+        width: usize,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Directive {
+    /// %p
+    Path,
+    /// %f
+    Name,
+    /// %h
+    Dir,
+    /// %s
+    Size,
+    /// %m
+    Mode,
+    /// %M
+    Permissions,
+    /// %u
+    User,
+    /// %g
+    Group,
+    /// %t
+    Time,
+    /// %T@
+    Epoch,
+    /// %y
+    Kind,
+    /// %l
+    Target,
+    /// %d
+    Depth,
+}
+
+impl Format {
+    /// the directives and backslash escapes find knows of; the ones it doesn't are only
+    /// warned about and printed as they are
+    fn parse(format: &str) -> MyResult<Self> {
+        let mut pieces = vec![];
+        let mut text = vec![];
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let escaped = match chars.next() {
+                        // \c stops the output right there
+                        Some('c') => break,
+                        Some('a') => 0x07,
+                        Some('b') => 0x08,
+                        Some('f') => 0x0c,
+                        Some('n') => b'\n',
+                        Some('r') => b'\r',
+                        Some('t') => b'\t',
+                        Some('v') => 0x0b,
+                        Some('\\') => b'\\',
+                        Some(digit @ '0'..='7') => {
+                            let mut value = digit.to_digit(8).unwrap();
+                            for _ in 0..2 {
+                                match chars.peek().and_then(|c| c.to_digit(8)) {
+                                    Some(digit) => {
+                                        value = value * 8 + digit;
+                                        chars.next();
+                                    }
+                                    None => break,
+                                }
+                            }
+                            value as u8
+                        }
+                        Some(other) => {
+                            eprintln!("warning: unrecognized escape '\\{}'", other);
+                            text.push(b'\\');
+                            push_char(&mut text, other);
+                            continue;
+                        }
+                        None => {
+                            eprintln!("warning: escape '\\' followed by nothing at all");
+                            b'\\'
+                        }
+                    };
+                    text.push(escaped);
+                }
+                '%' => {
+                    let left = chars.next_if_eq(&'-').is_some();
+                    let mut width = 0;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        width = width * 10 + digit as usize;
+                        chars.next();
+                    }
+                    let directive = match chars.next() {
+                        None => return Err("error: % at end of format string".into()),
+                        Some('%') => {
+                            text.push(b'%');
+                            continue;
+                        }
+                        Some('p') => Directive::Path,
+                        Some('f') => Directive::Name,
+                        Some('h') => Directive::Dir,
+                        Some('s') => Directive::Size,
+                        Some('m') => Directive::Mode,
+                        Some('M') => Directive::Permissions,
+                        Some('u') => Directive::User,
+                        Some('g') => Directive::Group,
+                        Some('t') => Directive::Time,
+                        Some('T') if chars.next_if_eq(&'@').is_some() => Directive::Epoch,
+                        Some('y') => Directive::Kind,
+                        Some('l') => Directive::Target,
+                        Some('d') => Directive::Depth,
+                        Some(other) => {
+                            eprintln!("warning: unrecognized format directive '%{}'", other);
+                            text.push(b'%');
+                            push_char(&mut text, other);
+                            continue;
+                        }
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field {
+                        directive,
+                        left,
+                        width,
+                    });
+                }
+                other => push_char(&mut text, other),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+
+        Ok(Format(pieces))
+    }
+
+    fn render(&self, entry: &Entry, out: &mut Vec<u8>) {
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => out.extend_from_slice(text),
+                Piece::Field {
+                    directive,
+                    left,
+                    width,
+                } => {
+                    let value = directive.value(entry);
+                    let padding = iter::repeat_n(b' ', width.saturating_sub(value.len()));
+                    if *left {
+                        out.extend_from_slice(&value);
+                        out.extend(padding);
+                    } else {
+                        out.extend(padding);
+                        out.extend_from_slice(&value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Directive {
+    /// what the directive stands for with `entry`, which is empty when the entry's metadata
+    /// can't be read
+    fn value<'a>(&self, entry: &'a Entry) -> Cow<'a, [u8]> {
+        let path = entry.path();
+        let metadata = match self {
+            Directive::Path => return bytes(path.as_os_str()),
+            Directive::Name => return base_name(bytes(path.as_os_str())),
+            Directive::Dir => return dir_name(bytes(path.as_os_str())),
+            Directive::Depth => return Cow::Owned(entry.depth().to_string().into_bytes()),
+            Directive::Kind => return Cow::Borrowed(kind(entry.file_type()).as_bytes()),
+            Directive::Target => {
+                // a link that's followed is what it points to
+                if !entry.file_type().is_symlink() {
+                    return Cow::Borrowed(b"");
+                }
+                return match fs::read_link(path) {
+                    Ok(target) => Cow::Owned(bytes(target.as_os_str()).into_owned()),
+                    Err(e) => {
+                        eprintln!("{}: {}", path.display(), e);
+                        entry.failed.set(true);
+                        Cow::Borrowed(b"")
+                    }
+                };
+            }
+            _ => match entry.metadata() {
+                Some(metadata) => metadata,
+                None => return Cow::Borrowed(b""),
+            },
+        };
+
+        let value = match self {
+            Directive::Size => metadata.len().to_string(),
+            Directive::Mode => format!("{:o}", mode(metadata) & 0o7777),
+            Directive::Permissions => permissions(kind(entry.file_type()), mode(metadata)),
+            Directive::User => user(metadata),
+            Directive::Group => group(metadata),
+            Directive::Time | Directive::Epoch => {
+                let modified = match metadata.modified() {
+                    Ok(modified) => modified,
+                    Err(_) => return Cow::Borrowed(b""),
+                };
+                if *self == Directive::Epoch {
+                    // find's precision is to the tenth of a nanosecond. before the epoch, the
+                    // seconds are floored and the nanoseconds count up from there
+                    let (secs, nanos) = match modified.duration_since(UNIX_EPOCH) {
+                        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+                        Err(e) => match e.duration().subsec_nanos() {
+                            0 => (-(e.duration().as_secs() as i64), 0),
+                            nanos => (-(e.duration().as_secs() as i64) - 1, 1_000_000_000 - nanos),
+                        },
+                    };
+                    format!("{}.{:09}0", secs, nanos)
+                } else {
+                    DateTime::<Local>::from(modified)
+                        .format("%a %b %e %H:%M:%S%.9f0 %Y")
+                        .to_string()
+                }
+            }
+            _ => unreachable!("{:?} doesn't need the metadata", self),
+        };
+        Cow::Owned(value.into_bytes())
+    }
+}
+
+fn push_char(text: &mut Vec<u8>, c: char) {
+    text.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+/// the letter of -type and %y for a file type
+fn kind(file_type: fs::FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_fifo() {
+            return "p";
+        } else if file_type.is_socket() {
+            return "s";
+        } else if file_type.is_block_device() {
+            return "b";
+        } else if file_type.is_char_device() {
+            return "c";
+        }
+    }
+    if file_type.is_symlink() {
+        "l"
+    } else if file_type.is_dir() {
+        "d"
+    } else if file_type.is_file() {
+        "f"
+    } else {
+        "U"
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.mode()
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+/// the permissions as ls shows them, like "-rwxr-xr-x"
+fn permissions(kind: &str, mode: u32) -> String {
+    let mut permissions = String::from(match kind {
+        "f" => "-",
+        other => other,
+    });
+    // the setuid, setgid and sticky bits go over the execute bits they come with
+    let specials = [(0o4000, 's'), (0o2000, 's'), (0o1000, 't')];
+    for (shift, (special, letter)) in [6, 3, 0].into_iter().zip(specials) {
+        let bits = mode >> shift;
+        permissions.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        permissions.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        permissions.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => letter,
+            (false, true) => letter.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    permissions
+}
+
+/// the name of the owner, or their id when they have no name
+#[cfg(unix)]
+fn user(metadata: &fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    match uzers::get_user_by_uid(metadata.uid()) {
+        Some(user) => user.name().to_string_lossy().into_owned(),
+        None => metadata.uid().to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn group(metadata: &fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    match uzers::get_group_by_gid(metadata.gid()) {
+        Some(group) => group.name().to_string_lossy().into_owned(),
+        None => metadata.gid().to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn user(_: &fs::Metadata) -> String {
+    String::new()
+}
+
+#[cfg(not(unix))]
+fn group(_: &fs::Metadata) -> String {
+    String::new()
+}
+
+/// the bytes of a name as they are, which aren't always UTF-8 on unix
+#[cfg(unix)]
+fn bytes(name: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(name.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn bytes(name: &OsStr) -> Cow<'_, [u8]> {
+    match name.to_string_lossy() {
+        Cow::Borrowed(name) => Cow::Borrowed(name.as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.into_bytes()),
+    }
+}
+
+/// where the last part of `path` starts and ends, leaving out the slashes after it; the end
+/// is 0 when the path is all slashes
+fn last_part(path: &[u8]) -> (usize, usize) {
+    let end = path
+        .iter()
+        .rposition(|&b| b'/' != b)
+        .map_or(0, |last| last + 1);
+    let start = path[..end]
+        .iter()
+        .rposition(|&b| b'/' == b)
+        .map_or(0, |slash| slash + 1);
+    (start, end)
+}
+
+fn sub_path(path: Cow<'_, [u8]>, start: usize, stop: usize) -> Cow<'_, [u8]> {
+    match path {
+        Cow::Borrowed(path) => Cow::Borrowed(&path[start..stop]),
+        Cow::Owned(path) => Cow::Owned(path[start..stop].to_vec()),
+    }
+}
+
+/// the last part of `path` for %f, which keeps a slash the path ends in ("dir/" stays
+/// "dir/") as GNU find does for a start path, and is "/" for the root
+fn base_name(path: Cow<'_, [u8]>) -> Cow<'_, [u8]> {
+    let (start, end) = last_part(&path);
+    if 0 == end {
+        return Cow::Borrowed(if path.is_empty() { b"" } else { b"/" });
+    }
+    let stop = path.len().min(end + 1);
+    sub_path(path, start, stop)
+}
+
+/// what comes before the last part of `path` for %h, less the slash in between, the way GNU
+/// find has it: "t//a" gives "t/", "/t" gives "" and a path with no slash gives ".". a start
+/// path that's a single part and slashes gives "." too, except that GNU find just drops the
+/// last slash when the part is a single char, or there's none ("t//" gives "t/", "//" "/")
+fn dir_name(path: Cow<'_, [u8]>) -> Cow<'_, [u8]> {
+    let stop = match last_part(&path) {
+        _ if !path.contains(&b'/') => return Cow::Borrowed(b"."),
+        (0, end) if end <= 1 => path.len() - 1,
+        (0, _) => return Cow::Borrowed(b"."),
+        (start, _) => start - 1,
+    };
+    sub_path(path, 0, stop)
+}
+
+/// -exec, -execdir, -ok and -okdir: a command run for each match, or for batches of them
+#[derive(Debug)]
+pub struct Exec {
+    /// the program and its arguments, where "{}" stands for the match
+    command: Vec<String>,
+    /// -execdir and -okdir run in the directory of the match, which is passed as "./name"
+    in_dir: bool,
+    /// -ok and -okdir ask before running
+    prompt: bool,
+    /// the matches waiting to be run with `command ... {} +`
+    batch: Option<Batch>,
+    /// whether a batch failed, which find's exit status tells about
+    failed: bool,
+}
+
+#[derive(Debug)]
+struct Batch {
+    /// the directory of the matches, with -execdir
+    dir: Option<PathBuf>,
+    paths: Vec<OsString>,
+    /// what the matches take up of the command line so far
+    size: usize,
+    /// the room there is for matches next to the command; with none, each gets run alone
+    room: usize,
+}
+
+impl Batch {
+    fn new(command: &[String]) -> Self {
+        Batch {
+            dir: None,
+            paths: vec![],
+            size: 0,
+            room: arg_max().saturating_sub(command_size(command)),
+        }
+    }
+}
+
+impl Exec {
+    /// the directory to run in and the match as the command gets it
+    fn target(&self, entry: &Entry) -> (Option<PathBuf>, OsString) {
+        if !self.in_dir {
+            return (None, entry.path().as_os_str().to_os_string());
+        }
+        let dir = match entry.path().parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut name = OsString::from("./");
+        name.push(entry.file_name());
+        (Some(dir), name)
+    }
+
+    fn eval(&mut self, entry: &Entry) -> bool {
+        let (dir, path) = self.target(entry);
+        if self.batch.is_none() {
+            let args = self.command[1..]
+                .iter()
+                .map(|arg| replace_braces(arg, &path))
+                .collect::<Vec<_>>();
+            if self.prompt && !confirm(&self.command[0], &args) {
+                return false;
+            }
+            return execute(&self.command[0], &args, dir.as_deref());
+        }
+
+        let size = arg_size(&path);
+        let batch = self.batch.as_mut().unwrap();
+        let full = batch.size + size > batch.room;
+        if !batch.paths.is_empty() && (batch.dir != dir || full) {
+            self.run_batch();
+        }
+        let batch = self.batch.as_mut().unwrap();
+        batch.dir = dir;
+        batch.paths.push(path);
+        batch.size += size;
+        true
+    }
+
+    fn run_batch(&mut self) {
+        let Some(batch) = self.batch.as_mut() else {
+            return;
+        };
+        if batch.paths.is_empty() {
+            return;
+        }
+        let paths = mem::take(&mut batch.paths);
+        let dir = batch.dir.take();
+        batch.size = 0;
+        // the command ends with the "{}" the matches go in place of
+        let args = self.command[1..self.command.len() - 1]
+            .iter()
+            .map(OsString::from)
+            .chain(paths);
+        if !execute(&self.command[0], args, dir.as_deref()) {
+            self.failed = true;
+        }
+    }
+
+    fn finish(&mut self) -> bool {
+        self.run_batch();
+        !self.failed
+    }
+}
+
+/// `arg` with every "{}" in it replaced by `path`
+fn replace_braces(arg: &str, path: &OsStr) -> OsString {
+    let mut replaced = OsString::new();
+    for (idx, part) in arg.split("{}").enumerate() {
+        if 0 < idx {
+            replaced.push(path);
+        }
+        replaced.push(part);
+    }
+    replaced
+}
+
+/// runs `program`, telling whether it exited with 0; a program that can't be started counts
+/// as failing
+fn execute<I, A>(program: &str, args: I, dir: Option<&Path>) -> bool
+where
+    I: IntoIterator<Item = A>,
+    A: AsRef<OsStr>,
+{
+    let mut command = process::Command::new(program);
+    command.args(args);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    match command.status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            eprintln!("{}: {}", program, e);
+            false
+        }
+    }
+}
+
+/// asks on stderr whether to run the command of -ok, which a line starting with "y" agrees to
+fn confirm(program: &str, args: &[OsString]) -> bool {
+    let mut question = format!("< {}", program);
+    for arg in args {
+        question.push(' ');
+        question.push_str(&arg.to_string_lossy());
+    }
+    eprint!("{} > ? ", question);
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => answer.starts_with(['y', 'Y']),
+        Err(_) => false,
+    }
+}
+
+/// how much of the argument space `arg` takes: itself, its NUL and the pointer to it
+fn arg_size(arg: &OsStr) -> usize {
+    arg.len() + 1 + mem::size_of::<usize>()
+}
+
+fn command_size(command: &[String]) -> usize {
+    command
+        .iter()
+        .map(|arg| arg_size(OsStr::new(arg)))
+        .sum::<usize>()
+}
+
+/// the room there is for the arguments of a command, which the environment shares; some is
+/// left over, as xargs does, so that the command can still add to its environment
+#[cfg(unix)]
+fn arg_max() -> usize {
+    let arg_max = match unsafe { libc::sysconf(libc::_SC_ARG_MAX) } {
+        max if 0 < max => max as usize,
+        _ => 128 * 1024,
+    };
+    let env_size = env::vars_os()
+        .map(|(key, value)| arg_size(&key) + value.len() + 1)
+        .sum::<usize>();
+    arg_max.saturating_sub(env_size + 2048)
+}
+
+#[cfg(not(unix))]
+fn arg_max() -> usize {
+    // what windows allows for a whole command line
+    32 * 1024 - 2048
+}
+
+/// writes out what was printed so far
+fn flush(out: &mut Vec<u8>) -> io::Result<()> {
+    if !out.is_empty() {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(out)?;
+        stdout.flush()?;
+        out.clear();
+    }
+    Ok(())
+}
+
+/// the ignore files of the directories the walk is in, for --gitignore
+struct Ignores {
+    /// git's global excludes
+    global: Gitignore,
+    /// the path the walk starts from, and where that is from the root
+    start: PathBuf,
+    absolute_start: PathBuf,
+    /// the ignore files above the start path up to the root of its git repository, innermost
+    /// first, then the repository's .git/info/exclude; they match absolute paths
+    parents: Vec<Gitignore>,
+    /// the .gitignore and .ignore of each directory, with the depth of the entries in it,
+    /// innermost last
+    dirs: Vec<(usize, Gitignore)>,
+    hidden: bool,
+}
+
+impl Ignores {
+    fn new(start: &Path, hidden: bool) -> Self {
+        let (global, error) = Gitignore::global();
+        if let Some(e) = error {
+            eprintln!("{}", e);
+        }
+        let absolute_start = fs::canonicalize(start).unwrap_or_else(|_| start.to_path_buf());
+
+        // like git, only the directories up to the one with .git in it are looked at
+        let mut parents = vec![];
+        let root = absolute_start
+            .ancestors()
+            .find(|dir| dir.join(".git").exists());
+        if let Some(root) = root {
+            for dir in absolute_start.ancestors().skip(1) {
+                if !dir.starts_with(root) {
+                    break;
+                }
+                parents.extend(ignore_file(dir, &[".gitignore", ".ignore"]));
+            }
+            parents.extend(ignore_file(root, &[".git/info/exclude"]));
+        }
+
+        Ignores {
+            global,
+            start: start.to_path_buf(),
+            absolute_start,
+            parents,
+            dirs: vec![],
+            hidden,
+        }
+    }
+
+    /// whether the walk should leave out `entry`; the ignore files of a directory that's kept
+    /// are read for what's in it
+    fn is_ignored(&mut self, entry: &Entry) -> bool {
+        let depth = entry.depth();
+        while self
+            .dirs
+            .last()
+            .is_some_and(|(dir_depth, _)| depth < *dir_depth)
+        {
+            self.dirs.pop();
+        }
+
+        // the paths to walk are always kept
+        let is_dir = entry.file_type().is_dir();
+        let ignored = 0 < depth
+            && ((!self.hidden && entry.file_name().to_string_lossy().starts_with('.'))
+                || self.matched(entry.path(), is_dir));
+
+        if is_dir && !ignored {
+            // .ignore comes last, so that it wins over .gitignore
+            if let Some(gitignore) = ignore_file(entry.path(), &[".gitignore", ".ignore"]) {
+                self.dirs.push((depth + 1, gitignore));
+            }
+        }
+        ignored
+    }
+
+    /// whether the innermost ignore file with a say on `path` ignores it
+    fn matched(&self, path: &Path, is_dir: bool) -> bool {
+        let absolute = match path.strip_prefix(&self.start) {
+            Ok(below) => self.absolute_start.join(below),
+            Err(_) => path.to_path_buf(),
+        };
+        self.dirs
+            .iter()
+            .rev()
+            .map(|(_, gitignore)| gitignore.matched(path, is_dir))
+            .chain(
+                self.parents
+                    .iter()
+                    .map(|gitignore| gitignore.matched(&absolute, is_dir)),
+            )
+            .chain(iter::once(self.global.matched(path, is_dir)))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+}
+
+/// the patterns of the `names` files in `dir` that exist, later ones winning over earlier
+/// ones; None when there are none
+fn ignore_file(dir: &Path, names: &[&str]) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    for name in names {
+        let file = dir.join(name);
+        if file.is_file() {
+            if let Some(e) = builder.add(file) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+    match builder.build() {
+        Ok(gitignore) if !gitignore.is_empty() => Some(gitignore),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     paths: Vec<PathBuf>,
-    ftypes: Vec<FindType>,
-    names: Vec<Regex>,
+    expr: Expr,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    /// leave out what git would ignore, and hidden entries unless `hidden`
+    gitignore: bool,
+    hidden: bool,
+    follow: Follow,
 }
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
+/// the symlink options, and how the primaries are written; the short and double-dash spellings
+/// of the first few are what findr took before it had an expression language
+const EXPRESSION_HELP: &str = "\
+Symlinks, in front of the paths:
+    -P                        never follow them (the default)
+    -L                        follow them; -type l only matches the broken ones
+    -H                        only follow the paths that are links
+
+Expression:
+  operators, from the highest precedence:
+    ( EXPR )                  group
+    ! EXPR, -not EXPR         negate
+    EXPR EXPR, EXPR -a EXPR   both (-and)
+    EXPR -o EXPR              either (-or)
+  tests:
+    -type d|f|l               (also -t, --type)
+    -name GLOB                base name matches GLOB (also -n, --name)
+    -iname GLOB               like -name, ignoring case (also --iname)
+    -regex REGEX              whole path matches REGEX (also --regex)
+    -iregex REGEX             like -regex, ignoring case (also --iregex)
+    -size [+-]N[bcwkMG]       size in units rounded up, 512 bytes by default; +N is more
+                              than N, -N less, and it goes the same for the numbers below
+    -mtime [+-]N              modified N days ago (-atime accessed, -ctime changed)
+    -mmin [+-]N               modified N minutes ago (also -amin, -cmin)
+    -newer FILE               modified after FILE was
+    -empty                    empty file or directory
+    -perm [-/]MODE            permissions are exactly MODE, -MODE has all of its bits,
+                              /MODE any of them; MODE is octal or like chmod's u+w,go=r
+    -user NAME, -uid [+-]N    owned by the user
+    -group NAME, -gid [+-]N   owned by the group
+  actions:
+    -print                    print the path; done for every match when there's no action
+    -print0                   print the path followed by a NUL
+    -prune                    don't go into the directory; true, and the match is printed
+    -printf FORMAT            print FORMAT, with \\n and the like, and these directives:
+                                %p path, %f name, %h directory, %s size, %m/%M permissions
+                                as octal/ls does, %u user, %g group, %t/%T@ modification
+                                time/as seconds, %y type, %l link target, %d depth
+    -exec CMD ;               run CMD, where {} is the path; true if it exits with 0
+    -exec CMD {} +            run CMD with as many paths at once as fit; always true
+    -execdir CMD ; (or +)     like -exec, but run in the directory of the match
+    -ok CMD ;                 like -exec, but ask first
+    -okdir CMD ;              like -execdir, but ask first
+  global options:
+    -maxdepth N               descend at most N levels below the paths (also --maxdepth)
+    -mindepth N               leave out what's less than N levels below (also --mindepth)
+    --gitignore               leave out what .gitignore, .ignore and git's global excludes
+                              ignore, and hidden entries
+    --hidden                  keep hidden entries with --gitignore";
+
+/// whether `arg` is where the expression starts, after the paths
+fn starts_expression(arg: &str) -> bool {
+    (arg.starts_with('-') && "-" != arg) || "(" == arg || "!" == arg
+}
+
 pub fn parse_args() -> MyResult<Config> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    // -P, -L and -H go in front of the paths, and the last one counts
+    let modes = args
+        .iter()
+        .take_while(|arg| matches!(arg.as_str(), "-P" | "-L" | "-H"))
+        .count();
+    let follow = match args[..modes].last().map(String::as_str) {
+        Some("-L") => Follow::Always,
+        Some("-H") => Follow::Paths,
+        _ => Follow::Never,
+    };
+    let args = &args[modes..];
+
+    let split = args
+        .iter()
+        .position(|arg| starts_expression(arg))
+        .unwrap_or(args.len());
+    // help and version are the only options clap sees, and only in front of everything else
+    let (options, expression) = match args[split..].first().map(String::as_str) {
+        Some("-h" | "--help" | "-V" | "--version") => args.split_at(split + 1),
+        _ => args.split_at(split),
+    };
+
     let matches = Command::new("findr")
         .version("0.1.0")
         .author("coolabhays")
         .about("simple clone of find in rust")
+        .override_usage("findr [-H] [-L] [-P] [paths]... [EXPRESSION]")
+        .after_help(EXPRESSION_HELP)
         .arg(
             Arg::new("paths")
                 .default_value(".")
@@ -32,115 +1348,523 @@ pub fn parse_args() -> MyResult<Config> {
                 .action(ArgAction::Append)
                 .help("Provide paths to search"),
         )
-        .arg(
-            arg!(-t --type <TYPE> "match type of the file")
-                .action(ArgAction::Append)
-                .value_parser(["d", "f", "l"])
-                .help("Types of file to search"),
-        )
-        .arg(
-            arg!(-n --name <NAME> "match the pattern for the result")
-                .action(ArgAction::Append)
-                .help("Patterns to search"),
-        )
-        .get_matches();
+        .get_matches_from(iter::once("findr").chain(options.iter().map(String::as_str)));
 
     let paths = matches
         .get_many::<String>("paths")
         .unwrap_or_default()
-        .map(|path| PathBuf::from(path)) // why do this ?
+        .map(PathBuf::from) // why do this ?
         .collect::<Vec<PathBuf>>();
 
-    let ftypes = matches
-        .get_many::<String>("type")
-        .unwrap_or_default()
-        .map(|t| t.as_str())
-        .map(|t| match t {
-            "d" => FindType::Dir,
-            "f" => FindType::File,
-            "l" => FindType::Link,
-            _ => unreachable!(),
-        })
-        .collect::<Vec<FindType>>();
-
-    let names = matches
-        .get_many::<String>("name")
-        .unwrap_or_default()
-        .map(move |n| Regex::new(n).unwrap())
-        .collect::<Vec<Regex>>();
+    let mut parser = Parser::new(expression, follow);
+    let mut expr = parser.parse()?;
+    if !expr.has_action() {
+        expr = Expr::And(Box::new(expr), Box::new(Expr::Print));
+    }
 
     Ok(Config {
         paths,
-        ftypes,
-        names,
+        expr,
+        max_depth: parser.max_depth,
+        min_depth: parser.min_depth,
+        gitignore: parser.gitignore,
+        hidden: parser.hidden,
+        follow,
     })
 }
 
-pub fn run(cfg: Config) -> MyResult<()> {
-    let type_filter = |entry: &DirEntry| {
-        cfg.ftypes.is_empty()
-            || cfg.ftypes.iter().any(|ft| match ft {
-                FindType::Link => entry.file_type().is_symlink(),
-                FindType::Dir => entry.file_type().is_dir(),
-                FindType::File => entry.file_type().is_file(),
-            })
-    };
+/// a recursive descent parser for find's operator grammar, where the global options it comes
+/// across are kept aside
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    gitignore: bool,
+    hidden: bool,
+    /// -newer follows FILE when the paths are followed
+    follow: Follow,
+    /// when find started, which -mtime and such count from
+    now: SystemTime,
+}
 
-    let name_filter = |entry: &DirEntry| {
-        return cfg.names.is_empty()
-            || cfg
-                .names
-                .iter()
-                .any(|name| name.is_match(&entry.file_name().to_string_lossy()));
-    };
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [String], follow: Follow) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            max_depth: None,
+            min_depth: None,
+            gitignore: false,
+            hidden: false,
+            follow,
+            now: SystemTime::now(),
+        }
+    }
+
+    fn parse(&mut self) -> MyResult<Expr> {
+        if self.tokens.is_empty() {
+            return Ok(Expr::True);
+        }
+        let expr = self.or()?;
+        match self.next() {
+            None => Ok(expr),
+            Some(")") => Err("you have too many ')'".into()),
+            Some(token) => Err(format!("unexpected '{}'", token).into()),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    /// the argument of `option`
+    fn value(&mut self, option: &str) -> MyResult<&'a str> {
+        self.next()
+            .ok_or_else(|| format!("missing argument to '{}'", option).into())
+    }
+
+    fn or(&mut self) -> MyResult<Expr> {
+        let mut expr = self.and()?;
+        while let Some(op @ ("-o" | "-or")) = self.peek() {
+            self.pos += 1;
+            let right = self.operand(op, Self::and)?;
+            expr = Expr::Or(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    /// two primaries in a row are joined with an implicit -and
+    fn and(&mut self) -> MyResult<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            let right = match self.peek() {
+                None | Some("-o" | "-or" | ")") => break,
+                Some(op @ ("-a" | "-and")) => {
+                    self.pos += 1;
+                    self.operand(op, Self::not)?
+                }
+                Some(_) => self.not()?,
+            };
+            expr = Expr::And(Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
 
-    const BUFSIZE: usize = 64;  // works best for me as I tested in unconventional way
-    let mut buffer: Vec<String> = vec![];
-    buffer.reserve(BUFSIZE);
+    fn not(&mut self) -> MyResult<Expr> {
+        match self.peek() {
+            Some(op @ ("!" | "-not")) => {
+                self.pos += 1;
+                let expr = self.operand(op, Self::not)?;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    /// what follows the operator `op`, which has to be there
+    fn operand(&mut self, op: &str, parse: fn(&mut Self) -> MyResult<Expr>) -> MyResult<Expr> {
+        match self.peek() {
+            None | Some("-o" | "-or" | "-a" | "-and" | ")") => {
+                Err(format!("expected an expression after '{}'", op).into())
+            }
+            _ => parse(self),
+        }
+    }
+
+    fn primary(&mut self) -> MyResult<Expr> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err("invalid expression; expected an expression".into()),
+        };
+
+        match token {
+            "(" => {
+                if let Some(")") = self.peek() {
+                    return Err("invalid expression; empty parentheses are not allowed".into());
+                }
+                let expr = self.or()?;
+                match self.next() {
+                    Some(")") => Ok(expr),
+                    _ => Err(
+                        "invalid expression; I was expecting to find a ')' somewhere \
+                              but did not see one"
+                            .into(),
+                    ),
+                }
+            }
+            "-o" | "-or" | "-a" | "-and" => Err(format!(
+                "invalid expression; you have used a binary operator '{}' with nothing before it",
+                token
+            )
+            .into()),
+            ")" => Err("you have too many ')'".into()),
+            "-type" | "-t" | "--type" => match self.value(token)? {
+                "d" => Ok(Expr::Type(FindType::Dir)),
+                "f" => Ok(Expr::Type(FindType::File)),
+                "l" => Ok(Expr::Type(FindType::Link)),
+                other => Err(format!(
+                    "invalid value '{}' for '{}': expected one of d, f, l",
+                    other, token
+                )
+                .into()),
+            },
+            "-name" | "-n" | "--name" => {
+                Ok(Expr::Name(NameMatch::glob(self.value(token)?, false)?))
+            }
+            "-iname" | "--iname" => Ok(Expr::Name(NameMatch::glob(self.value(token)?, true)?)),
+            "-regex" | "--regex" => Ok(Expr::Name(NameMatch::regex(self.value(token)?, false)?)),
+            "-iregex" | "--iregex" => Ok(Expr::Name(NameMatch::regex(self.value(token)?, true)?)),
+            "-size" => {
+                let value = self.value(token)?;
+                let (n, unit) = value.split_at(value.trim_end_matches(char::is_alphabetic).len());
+                let unit = match unit {
+                    "" | "b" => 512,
+                    "c" => 1,
+                    "w" => 2,
+                    "k" => 1024,
+                    "M" => 1024 * 1024,
+                    "G" => 1024 * 1024 * 1024,
+                    _ => return Err(format!("invalid -size type '{}'", unit).into()),
+                };
+                let comparison =
+                    Comparison::parse(n).ok_or_else(|| invalid_argument(value, token))?;
+                Ok(Expr::Meta(MetaTest::Size(comparison, unit)))
+            }
+            "-mtime" | "-mmin" | "-atime" | "-amin" | "-ctime" | "-cmin" => {
+                let field = match &token[1..2] {
+                    "a" => TimeField::Accessed,
+                    "c" => TimeField::Changed,
+                    _ => TimeField::Modified,
+                };
+                let unit = if token.ends_with("min") {
+                    60
+                } else {
+                    24 * 60 * 60
+                };
+                Ok(Expr::Meta(MetaTest::Time {
+                    field,
+                    comparison: self.number(token)?,
+                    unit,
+                    now: self.now,
+                }))
+            }
+            "-newer" => {
+                let file = self.value(token)?;
+                let metadata = if self.follow == Follow::Never {
+                    fs::symlink_metadata(file)
+                } else {
+                    fs::metadata(file)
+                };
+                let metadata = metadata.map_err(|e| format!("'{}': {}", file, e))?;
+                Ok(Expr::Meta(MetaTest::Newer(time(
+                    &metadata,
+                    TimeField::Modified,
+                ))))
+            }
+            "-empty" => Ok(Expr::Meta(MetaTest::Empty)),
+            "-perm" => {
+                let value = self.value(token)?;
+                let (how, mode) = match value.as_bytes().first() {
+                    Some(b'-') => (PermMatch::All, &value[1..]),
+                    Some(b'/') => (PermMatch::Any, &value[1..]),
+                    _ => (PermMatch::Exact, value),
+                };
+                match parse_mode(mode) {
+                    Some(bits) => Ok(Expr::Meta(MetaTest::Perm(how, bits))),
+                    None => Err(format!("invalid mode '{}'", value).into()),
+                }
+            }
+            "-uid" => Ok(Expr::Meta(MetaTest::Uid(self.number(token)?))),
+            "-gid" => Ok(Expr::Meta(MetaTest::Gid(self.number(token)?))),
+            "-user" => {
+                let name = self.value(token)?;
+                let uid = match uzers::get_user_by_name(name) {
+                    Some(user) => user.uid(),
+                    None => name
+                        .parse()
+                        .map_err(|_| format!("'{}' is not the name of a known user", name))?,
+                };
+                Ok(Expr::Meta(MetaTest::Uid(Comparison::Equal(uid.into()))))
+            }
+            "-group" => {
+                let name = self.value(token)?;
+                let gid = match uzers::get_group_by_name(name) {
+                    Some(group) => group.gid(),
+                    None => name
+                        .parse()
+                        .map_err(|_| format!("'{}' is not the name of an existing group", name))?,
+                };
+                Ok(Expr::Meta(MetaTest::Gid(Comparison::Equal(gid.into()))))
+            }
+            "-print" => Ok(Expr::Print),
+            "-prune" => Ok(Expr::Prune),
+            "-print0" => Ok(Expr::Print0),
+            "-printf" => Ok(Expr::Printf(Format::parse(self.value(token)?)?)),
+            "-exec" | "-execdir" | "-ok" | "-okdir" => self.exec(token),
+            "-maxdepth" | "--maxdepth" => {
+                self.max_depth = Some(self.depth(token)?);
+                Ok(Expr::True)
+            }
+            "-mindepth" | "--mindepth" => {
+                self.min_depth = Some(self.depth(token)?);
+                Ok(Expr::True)
+            }
+            "--gitignore" => {
+                self.gitignore = true;
+                Ok(Expr::True)
+            }
+            "--hidden" => {
+                self.hidden = true;
+                Ok(Expr::True)
+            }
+            _ if starts_expression(token) => Err(format!("unknown predicate '{}'", token).into()),
+            _ => Err(format!("paths must precede expression: '{}'", token).into()),
+        }
+    }
+
+    /// the command of -exec and its kin, which goes up to a ";", or a "+" right after "{}"
+    fn exec(&mut self, option: &str) -> MyResult<Expr> {
+        // -ok and -okdir ask for each match, so they have no "+"
+        let prompt = option.starts_with("-ok");
+        let mut command = vec![];
+        let batched = loop {
+            match self.next() {
+                None => return Err(format!("missing argument to '{}'", option).into()),
+                Some(";") => break false,
+                Some("+") if !prompt && command.last().is_some_and(|arg| "{}" == arg) => {
+                    break true
+                }
+                Some(arg) => command.push(arg.to_string()),
+            }
+        };
+        if command.is_empty() || (batched && 1 == command.len()) {
+            return Err(format!("missing argument to '{}'", option).into());
+        }
+
+        Ok(Expr::Exec(Exec {
+            batch: batched.then(|| Batch::new(&command)),
+            command,
+            in_dir: option.ends_with("dir"),
+            prompt,
+            failed: false,
+        }))
+    }
+
+    /// a number that can be more or less, like with -mtime
+    fn number(&mut self, option: &str) -> MyResult<Comparison> {
+        let value = self.value(option)?;
+        Comparison::parse(value).ok_or_else(|| invalid_argument(value, option))
+    }
+
+    fn depth(&mut self, option: &str) -> MyResult<usize> {
+        let value = self.value(option)?;
+        value.parse().map_err(|_| {
+            format!(
+                "invalid value '{}' for '{}': expected a non-negative integer",
+                value, option
+            )
+            .into()
+        })
+    }
+}
+
+fn invalid_argument(value: &str, option: &str) -> Box<dyn Error> {
+    format!("invalid argument '{}' to '{}'", value, option).into()
+}
+
+/// prints an error of the walk, in find's words for a loop
+fn report(error: &walkdir::Error) {
+    match (error.loop_ancestor(), error.path()) {
+        (Some(ancestor), Some(path)) => eprintln!(
+            "findr: File system loop detected; '{}' is part of the same file system loop as '{}'.",
+            path.display(),
+            ancestor.display()
+        ),
+        _ => eprintln!("findr: {}", error),
+    }
+}
+
+/// the exit status is 1 when an error was reported or a command of -exec ... + failed, as
+/// with find
+pub fn run(mut cfg: Config) -> MyResult<i32> {
+    const BUFSIZE: usize = 64; // works best for me as I tested in unconventional way
+    let mut buffer: Vec<u8> = Vec::with_capacity(BUFSIZE * 64);
+
+    // walkdir would raise max_depth to min_depth, where find has nothing to print
+    if let (Some(min_depth), Some(max_depth)) = (cfg.min_depth, cfg.max_depth) {
+        if min_depth > max_depth {
+            return Ok(0);
+        }
+    }
 
     let mut count = 0;
+    // as with find, errors of the walk or of reading an entry don't stop it but make the exit
+    // status 1
+    let mut failed = false;
     for path in cfg.paths {
-        for entry in WalkDir::new(&path) {
-            match entry {
-                Err(e) => eprintln!("{}", e),
-                Ok(entry) => {
-                    if name_filter(&entry) && type_filter(&entry) {
-                        buffer.push(entry.path().display().to_string());
+        // the depth limits go to walkdir, so directories below max_depth are never read; with
+        // --gitignore, the ones above min_depth are needed for their ignore files though
+        let mut walker = WalkDir::new(&path)
+            .follow_links(cfg.follow == Follow::Always)
+            .follow_root_links(cfg.follow != Follow::Never);
+        if let Some(depth) = cfg.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        let mut ignores = None;
+        if cfg.gitignore {
+            ignores = Some(Ignores::new(&path, cfg.hidden));
+        } else if let Some(depth) = cfg.min_depth {
+            walker = walker.min_depth(depth);
+        }
+
+        let mut entries = walker.into_iter();
+        while let Some(result) = entries.next() {
+            let entry = match &result {
+                Ok(dir_entry) => Entry::new(dir_entry, cfg.follow.at(dir_entry.depth())),
+                Err(e) => match Entry::broken_link(e) {
+                    Some(entry) => entry,
+                    None => {
+                        report(e);
+                        failed = true;
+                        continue;
                     }
-                    count += 1;
-                    if count == BUFSIZE {
-                        println!("{}", buffer.join("\n"));
-                        buffer.clear();
-                        count = 0;
+                },
+            };
+
+            if let Some(ignores) = &mut ignores {
+                if ignores.is_ignored(&entry) {
+                    if entry.file_type().is_dir() {
+                        entries.skip_current_dir();
                     }
+                    continue;
+                }
+                if entry.depth() < cfg.min_depth.unwrap_or(0) {
+                    continue;
                 }
             }
+
+            cfg.expr.eval(&entry, &mut buffer)?;
+            failed |= entry.failed.get();
+            if entry.pruned.get() && entry.file_type().is_dir() {
+                entries.skip_current_dir();
+            }
+            count += 1;
+            if count == BUFSIZE {
+                flush(&mut buffer)?;
+                count = 0;
+            }
         }
     }
-    if !buffer.is_empty() {
-        println!("{}", buffer.join("\n"));
-    }
+    flush(&mut buffer)?;
+    let succeeded = cfg.expr.finish();
 
-    Ok(())
+    Ok(if succeeded && !failed { 0 } else { 1 })
 }
 
 #[allow(dead_code)]
 fn regex_do_match(haystack: &str, needle: &str) -> bool {
     let reg = Regex::new(needle).unwrap();
-    reg.is_match(&haystack)
+    reg.is_match(haystack)
 }
 
 #[test]
 fn test_regex() {
-    assert_eq!(regex_do_match("this is good", "is g"), true);
-    assert_eq!(regex_do_match("this is good", ".*is g"), true);
-    assert_eq!(regex_do_match("this. is good", r"\..*is g"), true);
-    assert_eq!(regex_do_match("this. is good", "\\..*is g"), true);
+    assert!(regex_do_match("this is good", "is g"));
+    assert!(regex_do_match("this is good", ".*is g"));
+    assert!(regex_do_match("this. is good", r"\..*is g"));
+    assert!(regex_do_match("this. is good", "\\..*is g"));
 
     // rest, I'm trusting regex package here and will add tests if something I need needed to be
     // tested for my understanding
 }
 
+#[allow(dead_code)]
+fn parse_expr(expression: &str) -> Expr {
+    let tokens = expression
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    Parser::new(&tokens, Follow::Never).parse().unwrap()
+}
+
+#[test]
+fn test_precedence() {
+    // -a (and nothing at all) binds tighter than -o, and ! tighter than both
+    assert!(matches!(
+        parse_expr("-type f -name a -o -type d -a -name b"),
+        Expr::Or(left, right)
+            if matches!(*left, Expr::And(..)) && matches!(*right, Expr::And(..))
+    ));
+    assert!(matches!(
+        parse_expr("! -type f -name a"),
+        Expr::And(left, _) if matches!(*left, Expr::Not(..))
+    ));
+    assert!(matches!(
+        parse_expr("( -type f -o -type d ) -name a"),
+        Expr::And(left, _) if matches!(*left, Expr::Or(..))
+    ));
+    // and both are left associative
+    assert!(matches!(
+        parse_expr("-type f -o -type d -o -type l"),
+        Expr::Or(left, right)
+            if matches!(*left, Expr::Or(..)) && matches!(*right, Expr::Type(FindType::Link))
+    ));
+}
+
+#[test]
+fn test_permissions() {
+    assert_eq!(permissions("f", 0o644), "-rw-r--r--");
+    assert_eq!(permissions("d", 0o755), "drwxr-xr-x");
+    assert_eq!(permissions("f", 0o4755), "-rwsr-xr-x");
+    assert_eq!(permissions("f", 0o2644), "-rw-r-Sr--");
+    assert_eq!(permissions("d", 0o1777), "drwxrwxrwt");
+    assert_eq!(permissions("d", 0o1776), "drwxrwxrwT");
+}
+
+#[test]
+fn test_parse_mode() {
+    assert_eq!(parse_mode("644"), Some(0o644));
+    assert_eq!(parse_mode("4755"), Some(0o4755));
+    assert_eq!(parse_mode("u+w"), Some(0o200));
+    assert_eq!(parse_mode("+x"), Some(0o111));
+    assert_eq!(parse_mode("u=rwx,go=rx"), Some(0o755));
+    assert_eq!(parse_mode("a=rw,o-w"), Some(0o664));
+    assert_eq!(parse_mode("u+s,o+t"), Some(0o5000));
+    assert_eq!(parse_mode("17777"), None);
+    assert_eq!(parse_mode("8"), None);
+    assert_eq!(parse_mode("u+q"), None);
+    assert_eq!(parse_mode("w"), None);
+}
+
+#[test]
+fn test_comparison() {
+    assert_eq!(Comparison::parse("+3"), Some(Comparison::Greater(3)));
+    assert_eq!(Comparison::parse("-3"), Some(Comparison::Less(3)));
+    assert_eq!(Comparison::parse("3"), Some(Comparison::Equal(3)));
+    assert_eq!(Comparison::parse("+"), None);
+    assert_eq!(Comparison::parse("3k"), None);
+    // N minutes covers what's more than N - 1 up to N
+    assert!(Comparison::Equal(2).is_within(1.5));
+    assert!(!Comparison::Equal(2).is_within(2.5));
+    assert!(Comparison::Less(2).is_within(1.9));
+    assert!(Comparison::Greater(2).is_within(2.1));
+}
+
+#[test]
+fn test_batch_room() {
+    // a command too long to run with anything has no room left, rather than overflowing
+    let command = vec!["x".repeat(arg_max() + 1), "{}".to_string()];
+    assert_eq!(Batch::new(&command).room, 0);
+    assert!(0 < Batch::new(&["echo".to_string(), "{}".to_string()]).room);
+}
+
 // -----------------------------------------------------------
 
 /* for path in cfg.paths {
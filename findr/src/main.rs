@@ -1,8 +1,13 @@
+use std::process::exit;
+
 use findr::{parse_args, run};
 
 fn main() {
-    if let Err(e) = parse_args().and_then(run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    match parse_args().and_then(run) {
+        Ok(status) => exit(status),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
     }
 }
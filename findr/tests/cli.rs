@@ -1,4 +1,12 @@
-use std::{borrow::Cow, error::Error, fs};
+// the older tests borrow their argument arrays and elide a lifetime
+#![allow(clippy::needless_borrows_for_generic_args, mismatched_lifetime_syntaxes)]
+
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs,
+    time::{Duration, UNIX_EPOCH},
+};
 
 use assert_cmd::Command;
 use rand::{distributions::Alphanumeric, Rng};
@@ -81,7 +89,7 @@ fn unreadable_dir() -> TestResult {
     let cmd = Command::cargo_bin(PRG)?
         .arg("tests/inputs")
         .assert()
-        .success();
+        .failure();
     fs::remove_dir(dirname)?;
 
     let out = cmd.get_output();
@@ -96,6 +104,20 @@ fn unreadable_dir() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn missing_path_fails() -> TestResult {
+    // like find, the other paths still get walked
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args([&bad, "tests/inputs/g.csv"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr(predicates::str::contains(bad));
+
+    Ok(())
+}
+
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let file = format_file_name(expected_file);
     let contents = fs::read_to_string(file.as_ref())?;
@@ -240,7 +262,7 @@ fn type_l() -> TestResult {
 #[test]
 fn type_f_l() -> TestResult {
     run(
-        &["tests/inputs", "-t", "l", "-t", "f"],
+        &["tests/inputs", "-t", "l", "-o", "-t", "f"],
         "tests/expected/type_f_l.txt",
     )
 }
@@ -249,7 +271,7 @@ fn type_f_l() -> TestResult {
 #[test]
 fn name_csv() -> TestResult {
     run(
-        &["tests/inputs", "-n", ".*[.]csv"],
+        &["tests/inputs", "-n", "*.csv"],
         "tests/expected/name_csv.txt",
     )
 }
@@ -258,7 +280,7 @@ fn name_csv() -> TestResult {
 #[test]
 fn name_csv_mp3() -> TestResult {
     run(
-        &["tests/inputs", "-n", ".*[.]csv", "-n", ".*[.]mp3"],
+        &["tests/inputs", "-n", "*.csv", "-o", "-n", "*.mp3"],
         "tests/expected/name_csv_mp3.txt",
     )
 }
@@ -267,7 +289,7 @@ fn name_csv_mp3() -> TestResult {
 #[test]
 fn name_txt_path_a_d() -> TestResult {
     run(
-        &["tests/inputs/a", "tests/inputs/d", "--name", ".*.txt"],
+        &["tests/inputs/a", "tests/inputs/d", "--name", "*.txt"],
         "tests/expected/name_txt_path_a_d.txt",
     )
 }
@@ -275,14 +297,14 @@ fn name_txt_path_a_d() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn name_a() -> TestResult {
-    run(&["tests/inputs", "-n", "a"], "tests/expected/name_a.txt")
+    run(&["tests/inputs", "-n", "a*"], "tests/expected/name_a.txt")
 }
 
 // --------------------------------------------------
 #[test]
 fn type_f_name_a() -> TestResult {
     run(
-        &["tests/inputs", "-t", "f", "-n", "a"],
+        &["tests/inputs", "-t", "f", "-n", "a*"],
         "tests/expected/type_f_name_a.txt",
     )
 }
@@ -291,13 +313,742 @@ fn type_f_name_a() -> TestResult {
 #[test]
 fn type_d_name_a() -> TestResult {
     run(
-        &["tests/inputs", "--type", "d", "--name", "a"],
+        &["tests/inputs", "--type", "d", "--name", "a*"],
         "tests/expected/type_d_name_a.txt",
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn name_exact_a() -> TestResult {
+    run(&["tests/inputs", "-n", "a"], "tests/expected/name_exact_a.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn name_question() -> TestResult {
+    run(
+        &["tests/inputs", "-n", "?.txt"],
+        "tests/expected/name_question.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn name_class() -> TestResult {
+    run(
+        &["tests/inputs", "-n", "[b-d].*"],
+        "tests/expected/name_class.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn name_braces_are_literal() -> TestResult {
+    // fnmatch has no "{a,b}" alternation
+    let dir = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    fs::create_dir(&dir)?;
+    for file in ["a", "b", "{a,b}"] {
+        fs::write(dir.join(file), "")?;
+    }
+
+    let out = Command::cargo_bin(PRG)?
+        .current_dir(&dir)
+        .args([".", "-name", "{a,b}"])
+        .output();
+    fs::remove_dir_all(&dir)?;
+
+    assert_eq!(String::from_utf8(out?.stdout)?, "./{a,b}\n");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn iname_csv() -> TestResult {
+    run(
+        &["tests/inputs", "--iname", "*.CSV"],
+        "tests/expected/iname_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_csv() -> TestResult {
+    run(
+        &["tests/inputs", "--regex", r".*/[bg]\.csv"],
+        "tests/expected/regex_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_unanchored() -> TestResult {
+    run(
+        &["tests/inputs", "--regex", "csv"],
+        "tests/expected/regex_unanchored.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn iregex_d_txt() -> TestResult {
+    run(
+        &["tests/inputs", "--iregex", r".*/D/.*\.TXT"],
+        "tests/expected/iregex_d_txt.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--regex", "("])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("regex parse error"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_glob() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--name", "[a"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("unclosed character class"));
+
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn path_g() -> TestResult {
     run(&["tests/inputs/g.csv"], "tests/expected/path_g.txt")
 }
+
+// --------------------------------------------------
+#[test]
+fn maxdepth_0() -> TestResult {
+    run(
+        &["tests/inputs", "--maxdepth", "0"],
+        "tests/expected/maxdepth_0.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn maxdepth_1() -> TestResult {
+    run(
+        &["tests/inputs", "--maxdepth", "1"],
+        "tests/expected/maxdepth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn type_f_maxdepth_2() -> TestResult {
+    run(
+        &["tests/inputs", "-t", "f", "--maxdepth", "2"],
+        "tests/expected/type_f_maxdepth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_2() -> TestResult {
+    run(
+        &["tests/inputs", "--mindepth", "2"],
+        "tests/expected/mindepth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_4() -> TestResult {
+    run(
+        &["tests/inputs", "--mindepth", "4"],
+        "tests/expected/mindepth_4.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_1_maxdepth_1_path_a_d() -> TestResult {
+    run(
+        &[
+            "tests/inputs/a",
+            "tests/inputs/d",
+            "--mindepth",
+            "1",
+            "--maxdepth",
+            "1",
+        ],
+        "tests/expected/mindepth_1_maxdepth_1_path_a_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_3_maxdepth_2() -> TestResult {
+    run(
+        &["tests/inputs", "--mindepth", "3", "--maxdepth", "2"],
+        "tests/expected/mindepth_3_maxdepth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_maxdepth() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--maxdepth", "x"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid value 'x' for '--maxdepth'"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_or_and() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-name",
+            "*.csv",
+            "-o",
+            "-type",
+            "d",
+            "-name",
+            "a*",
+        ],
+        "tests/expected/expr_or_and.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_parens() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "(",
+            "-name",
+            "a*",
+            "-or",
+            "-name",
+            "b*",
+            ")",
+            "-and",
+            "-type",
+            "f",
+        ],
+        "tests/expected/expr_parens.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_not() -> TestResult {
+    run(
+        &["tests/inputs", "!", "-type", "d", "-not", "-name", "*.csv"],
+        "tests/expected/expr_not.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_print() -> TestResult {
+    // with an action, matches aren't printed on their own
+    run(
+        &["tests/inputs", "-name", "a*", "-print", "-o", "-type", "l"],
+        "tests/expected/expr_print.txt",
+    )
+}
+
+fn dies_expression(args: &[&str], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(expected));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_expressions() -> TestResult {
+    dies_expression(&["-o", "-type", "f"], "binary operator '-o' with nothing before it")?;
+    dies_expression(&["-type", "f", "-a"], "expected an expression after '-a'")?;
+    dies_expression(&["(", "-type", "f"], "expecting to find a ')'")?;
+    dies_expression(&["-type", "f", ")"], "you have too many ')'")?;
+    dies_expression(&["(", ")"], "empty parentheses are not allowed")?;
+    dies_expression(&["-name"], "missing argument to '-name'")?;
+    dies_expression(&["-foo"], "unknown predicate '-foo'")?;
+    dies_expression(&["-exec", "echo", "{}"], "missing argument to '-exec'")?;
+    dies_expression(&["-exec", "{}", "+"], "missing argument to '-exec'")?;
+    dies_expression(&["-ok", "echo", "{}", "+"], "missing argument to '-ok'")?;
+    dies_expression(&["-printf", "%p%"], "% at end of format string")?;
+    dies_expression(&["-size", "3q"], "invalid -size type 'q'")?;
+    dies_expression(&["-mtime", "x"], "invalid argument 'x' to '-mtime'")?;
+    dies_expression(&["-perm", "u+q"], "invalid mode 'u+q'")?;
+    dies_expression(&["-user", "no-such-user-here"], "is not the name of a known user")?;
+    dies_expression(&["-type", "f", "tests"], "paths must precede expression: 'tests'")
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_echo() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-exec",
+            "echo",
+            "found",
+            "{}",
+            ";",
+        ],
+        "tests/expected/exec_echo.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn execdir_batch() -> TestResult {
+    // a batch for each directory, with the names relative to it
+    run(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.csv",
+            "-execdir",
+            "echo",
+            "{}",
+            "+",
+        ],
+        "tests/expected/execdir_batch.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_false_or() -> TestResult {
+    // a failing command is a false test, but doesn't fail find
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "d",
+            "-exec",
+            "false",
+            ";",
+            "-o",
+            "-type",
+            "d",
+            "-print",
+        ],
+        "tests/expected/exec_false_or.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_batch_fails() -> TestResult {
+    // while a failing batch does
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-exec", "false", "{}", "+"])
+        .assert()
+        .code(1)
+        .stdout("");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ok_asks() -> TestResult {
+    let args = ["tests/inputs/g.csv", "-ok", "echo", "{}", ";"];
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr("< echo tests/inputs/g.csv > ? ");
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout("");
+
+    Ok(())
+}
+
+/// like `run`, for output where a NUL ends each path
+fn run_nul(args: &[&str], expected_file: &str) -> TestResult {
+    let file = format_file_name(expected_file);
+    let contents = fs::read(file.as_ref())?;
+    let mut expected = contents.split(|&b| b == 0).collect::<Vec<_>>();
+    expected.sort();
+
+    let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
+    let out = cmd.get_output();
+    let mut lines = out.stdout.split(|&b| b == 0).collect::<Vec<_>>();
+    lines.sort();
+
+    assert_eq!(expected, lines);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn print0() -> TestResult {
+    run_nul(&["tests/inputs", "-print0"], "tests/expected/print0.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn printf() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-printf",
+            "%y %d %m %M %-3s|%h|%f|%l|\\t%p\\n",
+        ],
+        "tests/expected/printf.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_escapes() -> TestResult {
+    run_nul(
+        &["tests/inputs", "-type", "f", "-printf", "%%\\101\\t%f\\0"],
+        "tests/expected/printf_escapes.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_stops_at_c() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "-printf", "%f\\c%p"])
+        .assert()
+        .success()
+        .stdout("g.csv");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_name_keeps_trailing_slash() -> TestResult {
+    // as in GNU find, only a start path can end in a slash
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/", "tests/inputs/a//", "/", "-maxdepth", "0", "-printf", "%f|"])
+        .assert()
+        .success()
+        .stdout("inputs/|a/|/|");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_dir_of_trailing_slash() -> TestResult {
+    // GNU find leaves out the slash before the last part, and a start path that's a single
+    // char and slashes only loses its last one
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a/", "tests/inputs//a/b", "tests/", "-maxdepth", "0"])
+        .args(["-printf", "%h|"])
+        .assert()
+        .success()
+        .stdout("tests/inputs|tests/inputs//a|.|");
+    Command::cargo_bin(PRG)?
+        .current_dir("tests/inputs")
+        .args(["a//", "-maxdepth", "0", "-printf", "%h|"])
+        .assert()
+        .success()
+        .stdout("a/|");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_epoch_before_1970() -> TestResult {
+    // the seconds are floored, so half a second before the epoch is -1 and a half
+    let file = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    fs::File::create(&file)?.set_modified(UNIX_EPOCH - Duration::from_millis(500))?;
+    let out = Command::cargo_bin(PRG)?
+        .arg(&file)
+        .args(["-printf", "%T@"])
+        .output();
+    fs::remove_file(&file)?;
+
+    assert_eq!(String::from_utf8(out?.stdout)?, "-1.5000000000");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_warns_unknown() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "-printf", "%z\\q"])
+        .assert()
+        .success()
+        .stdout("%z\\q")
+        .stderr(predicates::str::contains(
+            "warning: unrecognized format directive '%z'",
+        ));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn size() -> TestResult {
+    run(&["tests/inputs", "-size", "2c"], "tests/expected/size_2c.txt")?;
+    // sizes are rounded up to the unit
+    run(&["tests/inputs", "-size", "1k"], "tests/expected/size_1k.txt")?;
+    run(
+        &["tests/inputs", "-size", "+1k", "-o", "-size", "-1"],
+        "tests/expected/size_more_1k_less_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn perm() -> TestResult {
+    run(
+        &["tests/inputs", "-perm", "-o+w"],
+        "tests/expected/perm_all_o_w.txt",
+    )?;
+    run(
+        &["tests/inputs", "-perm", "/u+x"],
+        "tests/expected/perm_any_u_x.txt",
+    )?;
+    run(
+        &["tests/inputs", "-type", "f", "-perm", "664"],
+        "tests/expected/perm_664.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mtime() -> TestResult {
+    run(
+        &["tests/inputs", "-mtime", "-36500"],
+        "tests/expected/mtime_less_36500.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_newer_mmin() -> TestResult {
+    use std::time::{Duration, SystemTime};
+
+    let dir = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    fs::create_dir(&dir)?;
+    fs::create_dir(dir.join("empty"))?;
+    fs::write(dir.join("full.txt"), "x")?;
+    let old = fs::File::create(dir.join("old.txt"))?;
+    old.set_modified(SystemTime::now() - Duration::from_secs(90 * 60))?;
+    drop(old);
+
+    let find = |args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let out = Command::cargo_bin(PRG)?
+            .arg(&dir)
+            .args(args)
+            .args(["-printf", "%f\\n"])
+            .assert()
+            .success();
+        let mut names = String::from_utf8(out.get_output().stdout.clone())?
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names.join(" "))
+    };
+    let results = [
+        find(&["-empty"]),
+        find(&["-type", "f", "-newer", &dir.join("old.txt").to_string_lossy()]),
+        find(&["-type", "f", "-mmin", "+60"]),
+        find(&["-type", "f", "-mmin", "-60"]),
+    ];
+    fs::remove_dir_all(&dir)?;
+
+    let [empty, newer, older, recent] = results;
+    assert_eq!(empty?, "empty old.txt");
+    assert_eq!(newer?, "full.txt");
+    assert_eq!(older?, "old.txt");
+    assert_eq!(recent?, "full.txt");
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn prune() -> TestResult {
+    run(
+        &["tests/inputs", "-name", "a", "-prune", "-o", "-print"],
+        "tests/expected/prune_a.txt",
+    )?;
+    run(
+        &[
+            "tests/inputs", "-type", "d", "-name", "b", "-prune", "-o", "-type", "f", "-print",
+        ],
+        "tests/expected/prune_b_type_f.txt",
+    )?;
+    // without another action, what's pruned is printed
+    run(
+        &["tests/inputs", "-name", "*.csv", "-prune"],
+        "tests/expected/prune_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    for sub in ["target/debug", ".git/objects", "src/gen"] {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+    for file in ["target/debug/x", "src/main.rs", "src/gen/out.rs", "src/a.tmp", "src/b.tmp"] {
+        fs::write(dir.join(file), "")?;
+    }
+    fs::write(dir.join(".gitignore"), "target\n")?;
+    fs::write(dir.join("src/.gitignore"), "*.tmp\n")?;
+    // .ignore wins over .gitignore
+    fs::write(dir.join("src/.ignore"), "gen/\n!a.tmp\n")?;
+
+    let find = |args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let out = Command::cargo_bin(PRG)?
+            .arg(&dir)
+            .args(args)
+            .args(["-mindepth", "1"])
+            // keep the global excludes of whoever runs the tests out of it
+            .env("HOME", &dir)
+            .env_remove("XDG_CONFIG_HOME")
+            .assert()
+            .success();
+        let prefix = format!("{}/", dir.display());
+        let mut paths = String::from_utf8(out.get_output().stdout.clone())?
+            .lines()
+            .map(|path| path.trim_start_matches(&prefix).to_string())
+            .collect::<Vec<_>>();
+        paths.sort();
+        Ok(paths.join(" "))
+    };
+    let results = [find(&["--gitignore"]), find(&["--gitignore", "--hidden"])];
+    fs::remove_dir_all(&dir)?;
+
+    let [ignored, hidden] = results;
+    assert_eq!(ignored?, "src src/a.tmp src/main.rs");
+    assert_eq!(
+        hidden?,
+        ".git .git/objects .gitignore src src/.gitignore src/.ignore src/a.tmp src/main.rs"
+    );
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore_from_subdir() -> TestResult {
+    // the ignore files above the start path count too, up to the root of the repository
+    let dir = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    for sub in [".git/info", "sub/target", "sub/gen"] {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+    for file in ["sub/target/x", "sub/gen/y", "sub/a.log", "sub/main.rs"] {
+        fs::write(dir.join(file), "")?;
+    }
+    fs::write(dir.join(".gitignore"), "target/\n/sub/gen\n")?;
+    fs::write(dir.join(".git/info/exclude"), "*.log\n")?;
+
+    let out = Command::cargo_bin(PRG)?
+        .current_dir(dir.join("sub"))
+        .args([".", "--gitignore"])
+        .env("HOME", &dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .output();
+    fs::remove_dir_all(&dir)?;
+
+    let mut paths = String::from_utf8(out?.stdout)?
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, [".", "./main.rs"]);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow() -> TestResult {
+    run(&["-L", "tests/inputs"], "tests/expected/follow_l.txt")?;
+    run(
+        &["-L", "tests/inputs", "-type", "f"],
+        "tests/expected/follow_l_type_f.txt",
+    )?;
+    // a link that's followed is what it points to
+    run(
+        &["-L", "tests/inputs", "-type", "l"],
+        "tests/expected/follow_l_type_l.txt",
+    )?;
+    run(
+        &["-H", "tests/inputs", "-type", "l"],
+        "tests/expected/follow_h_type_l.txt",
+    )?;
+    // the last one counts
+    run(
+        &["-L", "-P", "tests/inputs", "-type", "l"],
+        "tests/expected/type_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[cfg(unix)]
+#[test]
+fn follow_broken_and_loops() -> TestResult {
+    use std::os::unix::fs::symlink;
+
+    let dir = std::env::temp_dir().join(format!("findr-{}", get_bad_files()));
+    fs::create_dir_all(dir.join("sub"))?;
+    symlink("nowhere", dir.join("broken"))?;
+    symlink("..", dir.join("sub/up"))?;
+
+    let find = |args: &[&str]| {
+        Command::cargo_bin(PRG)
+            .map(|mut cmd| cmd.args(args).arg(&dir).args(["-type", "l"]).assert())
+    };
+    let (broken, path_link) = (find(&["-L"]), find(&["-H"]));
+    fs::remove_dir_all(&dir)?;
+
+    // only the broken link is still a link, and the loop is left out but fails the run
+    broken?
+        .failure()
+        .stdout(format!("{}\n", dir.join("broken").display()))
+        .stderr(predicates::str::contains(format!(
+            "findr: File system loop detected; '{}' is part of the same file system loop as '{}'.",
+            dir.join("sub/up").display(),
+            dir.display()
+        )));
+    // -H doesn't follow what's found below the paths
+    path_link?.success().stdout(predicates::str::contains("up\n"));
+
+    Ok(())
+}
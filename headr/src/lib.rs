@@ -1,8 +1,10 @@
-use std::{num::NonZeroUsize, fs::File, io::{BufReader, self, Read}};
+use std::{num::NonZeroUsize, fs::File, io::{BufReader, self, Read, Write}, collections::VecDeque};
 #[allow(unused_imports)]
 use std::{error::Error, io::BufRead};
 
 use clap::{Parser, ArgGroup};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use regex::bytes::Regex;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
@@ -15,6 +17,11 @@ type MyResult<T> = Result<T, Box<dyn Error>>;
         ArgGroup::new("count")
         .args(["bytes", "lines"])
         ))]
+#[command(group(
+        ArgGroup::new("stop")
+        .args(["until", "while_"])
+        .conflicts_with("bytes")
+        ))]
 pub struct Config {
     /// provide filenames
     #[arg(action = clap::ArgAction::Append)]
@@ -22,9 +29,33 @@ pub struct Config {
     /// print the first N bytes of file<s>
     #[arg(short = 'c', long)]
     bytes: Option<NonZeroUsize>,
-    /// print first N lines of file<s>
-    #[arg(short = 'n', long, default_value = "10")]
-    lines: NonZeroUsize,
+    /// print first N lines of file<s> (default 10, unbounded with --until/--while)
+    #[arg(short = 'n', long)]
+    lines: Option<NonZeroUsize>,
+    /// line delimiter is NUL, not newline
+    #[arg(short = 'z', long, conflicts_with = "delimiter")]
+    zero_terminated: bool,
+    /// use BYTE as the line delimiter (a single byte, or one of \0 \t \n \\)
+    #[arg(long, value_name = "BYTE", value_parser = parse_delimiter)]
+    delimiter: Option<u8>,
+    /// print lines up to the first one matching REGEX
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    until: Option<Regex>,
+    /// print lines as long as they match REGEX
+    #[arg(long = "while", value_name = "REGEX", value_parser = Regex::new)]
+    while_: Option<Regex>,
+    /// also print the line on which --until/--while stopped
+    #[arg(long, requires = "stop")]
+    inclusive: bool,
+    /// print the first N and the last N lines, with a `...` marker for the lines in between
+    #[arg(long, conflicts_with_all = ["bytes", "stop", "sample"])]
+    peek: bool,
+    /// print K lines picked at random (in their original order)
+    #[arg(long, value_name = "K", conflicts_with_all = ["bytes", "lines", "stop"])]
+    sample: Option<NonZeroUsize>,
+    /// seed for --sample, to pick the same lines on every run
+    #[arg(long, requires = "sample")]
+    seed: Option<u64>,
 }
 
 pub fn get_args() -> MyResult<Config> {
@@ -38,61 +69,209 @@ pub fn get_args() -> MyResult<Config> {
         Err(format!("illegal line count -- {}", cli.line_count))
     } */
 
+    if cli.zero_terminated {
+        cli.delimiter = Some(b'\0');
+    }
+
+    if cli.lines.is_none() && cli.until.is_none() && cli.while_.is_none() {
+        cli.lines = NonZeroUsize::new(10);
+    }
+
     Ok(Config {
         filenames: cli.filenames,
         bytes: cli.bytes,
-        lines: cli.lines
+        lines: cli.lines,
+        zero_terminated: cli.zero_terminated,
+        delimiter: cli.delimiter,
+        until: cli.until,
+        while_: cli.while_,
+        inclusive: cli.inclusive,
+        peek: cli.peek,
+        sample: cli.sample,
+        seed: cli.seed,
     })
 }
 
-#[allow(unused_variables)]
-pub fn run(config: Config) -> MyResult<()> {
+/// prints the head of every file, reporting failures as it goes, and returns the exit status:
+/// 1 if any input couldn't be opened or read, 0 otherwise
+pub fn run(config: Config) -> MyResult<i32> {
+    let mut status = 0;
 
     for (filenum, filename) in config.filenames.iter().enumerate() {
         if config.filenames.len() > 1 {
             println!("{}==> {} <==", if 0 == filenum { "" } else { "\n" }, filename);
         }
-        match open(filename) {
-            Err(e) => eprintln!("{}: {}", filename, e),
-            Ok(mut buf) => read(&mut buf, &config)?,
+        if let Err(e) = open(filename).and_then(|input| read(input, &config)) {
+            eprintln!("{}: {}", filename, e);
+            status = 1;
         }
     }
 
-    Ok(())
+    Ok(status)
 }
 
-fn open(filename: &str) -> MyResult<BufReader<Box<dyn Read>>> {
+/// opens the input unbuffered, so that byte mode only consumes what it prints
+fn open(filename: &str) -> MyResult<Box<dyn Read>> {
     match filename {
-        "-" => Ok(BufReader::new(Box::new(io::stdin()))),
-        _ => Ok(BufReader::new(Box::new(File::open(&filename)?))),
+        "-" => Ok(stdin_raw()?),
+        _ => Ok(Box::new(File::open(filename)?)),
     }
 }
 
-fn read(buf_read: &mut BufReader<Box<dyn Read>>, cfg: &Config) -> MyResult<()> {
+/// std's `Stdin` reads ahead into its own buffer, so read from a duplicate of the descriptor
+/// instead; this keeps stdin positioned right after the bytes headr consumed
+#[cfg(unix)]
+fn stdin_raw() -> MyResult<Box<dyn Read>> {
+    use std::os::fd::AsFd;
+
+    Ok(Box::new(File::from(io::stdin().as_fd().try_clone_to_owned()?)))
+}
+
+#[cfg(not(unix))]
+fn stdin_raw() -> MyResult<Box<dyn Read>> {
+    Ok(Box::new(io::stdin()))
+}
+
+fn read(input: Box<dyn Read>, cfg: &Config) -> MyResult<()> {
+    // what was read goes out as it is, whether it's valid UTF-8 or not
+    let mut out = io::stdout().lock();
     if let Some(byte_count) = cfg.bytes {
-        /* let mut buff = vec![0; byte_count.into()];
-        let bytes_read = buf_read.read(&mut buff[..])?; */
-        // print!("{}", String::from_utf8_lossy(&buff[..bytes_read]));
-        let read_bytes = buf_read.bytes().take(byte_count.into()).collect::<Result<Vec<_>, _>>();
-        print!("{}", String::from_utf8_lossy(&read_bytes?[..]));
+        // `take` bounds every read to what is left, so nothing past the count is pulled in
+        io::copy(&mut input.take(byte_count.get() as u64), &mut out)?;
     } else {
-        /* for line in buf_read.lines().take(cfg.lines.into()) {
-            println!("{}", line?);
-        } */
-        let mut line = String::new();
-        for _ in 0..cfg.lines.into() {
-            let bytes = buf_read.read_line(&mut line)?;
-            if 0 == bytes {
-                break;
+        let delimiter = cfg.delimiter.unwrap_or(b'\n');
+        let buf_read = BufReader::new(input);
+        if cfg.peek {
+            let n = cfg.lines.map_or(10, NonZeroUsize::get);
+            read_peek(buf_read, &mut out, delimiter, n)?;
+        } else if let Some(k) = cfg.sample {
+            let rng = match cfg.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            };
+            read_sample(buf_read, &mut out, delimiter, k.get(), rng)?;
+        } else {
+            read_lines(buf_read, &mut out, delimiter, cfg)?;
+        }
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn read_lines(
+    mut buf_read: impl BufRead,
+    out: &mut impl Write,
+    delimiter: u8,
+    cfg: &Config,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    let mut printed = 0;
+    while cfg.lines.is_none_or(|n| printed < n.get()) {
+        let bytes = buf_read.read_until(delimiter, &mut line)?;
+        if 0 == bytes {
+            break;
+        }
+        if is_stop(&line, delimiter, cfg) {
+            if cfg.inclusive {
+                out.write_all(&line)?;
+            }
+            break;
+        }
+        out.write_all(&line)?;
+        printed += 1;
+        line.clear();
+    }
+
+    Ok(())
+}
+
+/// prints the first and last `n` lines; the tail is kept in a ring of `n` lines, so memory
+/// doesn't grow with the size of the input
+fn read_peek(
+    mut buf_read: impl BufRead,
+    out: &mut impl Write,
+    delimiter: u8,
+    n: usize,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    let mut tail: VecDeque<Vec<u8>> = VecDeque::with_capacity(n);
+    let mut seen = 0;
+    let mut omitted = 0;
+    loop {
+        line.clear();
+        if 0 == buf_read.read_until(delimiter, &mut line)? {
+            break;
+        }
+        seen += 1;
+        if seen <= n {
+            out.write_all(&line)?;
+        } else {
+            if tail.len() == n {
+                tail.pop_front();
+                omitted += 1;
             }
-            print!("{}", &line);
-            line.clear();
+            tail.push_back(line.clone());
         }
     }
 
+    if 0 < omitted {
+        write!(out, "... ({} lines omitted)", omitted)?;
+        out.write_all(&[delimiter])?;
+    }
+    for line in tail {
+        out.write_all(&line)?;
+    }
+
     Ok(())
 }
 
+/// picks `k` lines with reservoir sampling, so every line is equally likely to be picked
+/// without holding more than `k` of them, and prints them in the order they were read
+fn read_sample(
+    mut buf_read: impl BufRead,
+    out: &mut impl Write,
+    delimiter: u8,
+    k: usize,
+    mut rng: impl Rng,
+) -> MyResult<()> {
+    let mut line = Vec::new();
+    let mut reservoir: Vec<(usize, Vec<u8>)> = Vec::with_capacity(k);
+    let mut seen = 0;
+    loop {
+        line.clear();
+        if 0 == buf_read.read_until(delimiter, &mut line)? {
+            break;
+        }
+        if reservoir.len() < k {
+            reservoir.push((seen, line.clone()));
+        } else {
+            let slot = rng.gen_range(0..=seen);
+            if slot < k {
+                reservoir[slot] = (seen, line.clone());
+            }
+        }
+        seen += 1;
+    }
+
+    reservoir.sort_by_key(|(idx, _)| *idx);
+    for (_, line) in reservoir {
+        out.write_all(&line)?;
+    }
+
+    Ok(())
+}
+
+/// whether `line` ends the output because of --until/--while, matched without its delimiter
+fn is_stop(line: &[u8], delimiter: u8, cfg: &Config) -> bool {
+    let record = line.strip_suffix(&[delimiter]).unwrap_or(line);
+    match (&cfg.until, &cfg.while_) {
+        (Some(until), _) => until.is_match(record),
+        (_, Some(while_)) => !while_.is_match(record),
+        _ => false,
+    }
+}
+
 #[allow(dead_code)]
 fn print_type_of<T>(_: &T) {
     println!("{}", std::any::type_name::<T>());
@@ -109,6 +288,18 @@ fn parse_positive_int(num: &str) -> MyResult<usize> {
     }  // the syntax inside match is "match guard"
 }
 
+/// parses the argument of `--delimiter` into the byte records are split on
+fn parse_delimiter(delim: &str) -> Result<u8, String> {
+    match delim {
+        "\\0" => Ok(b'\0'),
+        "\\t" => Ok(b'\t'),
+        "\\n" => Ok(b'\n'),
+        "\\\\" => Ok(b'\\'),
+        _ if delim.len() == 1 => Ok(delim.as_bytes()[0]),
+        _ => Err(format!("delimiter must be a single byte, got '{}'", delim)),
+    }
+}
+
 // unit test
 #[test]
 fn test_parse_positive_int() {
@@ -128,5 +319,18 @@ fn test_parse_positive_int() {
     assert_eq!(res.unwrap_err().to_string(), "0".to_string());
 }
 
+#[test]
+fn test_parse_delimiter() {
+    assert_eq!(parse_delimiter(","), Ok(b','));
+    assert_eq!(parse_delimiter("\\0"), Ok(b'\0'));
+    assert_eq!(parse_delimiter("\\t"), Ok(b'\t'));
+    assert_eq!(parse_delimiter("\\\\"), Ok(b'\\'));
+
+    // more than one byte is an error, even for a single non-ascii char
+    assert!(parse_delimiter("ab").is_err());
+    assert!(parse_delimiter("Ö").is_err());
+    assert!(parse_delimiter("").is_err());
+}
+
 /* fn open(filename: &String) -> MyResult<BufRead> {
 } */
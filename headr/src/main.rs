@@ -1,8 +1,11 @@
 use std::process::exit;
 
 fn main() {
-    if let Err(e) = headr::get_args().and_then(headr::run) {
-        eprintln!("{}", e);
-        exit(1);
+    match headr::get_args().and_then(headr::run) {
+        Ok(status) => exit(status),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
     }
 }
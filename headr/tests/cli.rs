@@ -1,4 +1,7 @@
-use std::{error::Error, fs::{self, File}, io::Read};
+// the older tests borrow their argument arrays
+#![allow(clippy::needless_borrows_for_generic_args)]
+
+use std::{error::Error, fs};
 
 use assert_cmd::Command;
 use predicates::prelude::predicate;
@@ -38,8 +41,22 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .args([EMPTY, &bad_file, ONE])
         .assert()
-        .success()  // the command will succeed
-        .stderr(predicate::str::contains(expected));
+        .failure()  // the other files are still printed, but the status reports the bad one
+        .stderr(predicate::str::contains(expected))
+        .stdout(predicate::str::contains("==> tests/inputs/one.txt <=="));
+
+    Ok(())
+}
+
+#[test]
+fn continues_after_read_error() -> TestResult {
+    // a directory opens fine, but fails on the first read
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", TWO])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("tests/inputs: Is a directory"))
+        .stdout(predicate::str::contains("Two lines.\nFour words.\n"));
 
     Ok(())
 }
@@ -83,35 +100,27 @@ fn dies_bytes_and_lines() -> TestResult {
 }
 
 fn run(args: &[&str], filename: &str) -> TestResult {
-    let mut file = File::open(filename)?;
-    let mut buff = Vec::new();
-    file.read_to_end(&mut buff)?;
-    let expected = String::from_utf8_lossy(&buff);
-    // let expected = fs::read_to_string(filename)?;
+    // head doesn't touch the bytes, so neither are they made valid UTF-8 here
+    let expected = fs::read(filename)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
-        // .stdout(expected);
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
 
 fn run_stdin(args: &[&str], input_filename: &str, expected_file: &str) -> TestResult {
-    let mut file = File::open(expected_file)?;
-    let mut buff = Vec::new();
-    file.read_to_end(&mut buff)?;
-    let expected = String::from_utf8_lossy(&buff);
-    let input = fs::read_to_string(input_filename)?;
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_filename)?;
 
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
-        // .stdout(buff);  // try something like this too, cause it's also a slice of u8
+        .stdout(predicate::eq(expected));
 
     Ok(())
 }
@@ -399,3 +408,288 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[cfg(unix)]
+fn run_shared_stdin(script: &str, input_filename: &str) -> TestResult {
+    let headr = assert_cmd::cargo::cargo_bin(PRG);
+    let input = fs::read(input_filename)?;
+    let mut expected = input[..10].to_vec();
+    expected.extend_from_slice(b"--\n");
+    expected.extend_from_slice(&input[10..]);
+
+    Command::new("sh")
+        .args(["-c", script, "sh", &headr.display().to_string(), input_filename])
+        .assert()
+        .success()
+        .stdout(predicate::eq(expected));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn bytes_leave_stdin_positioned() -> TestResult {
+    // redirected regular file
+    run_shared_stdin(r#"{ "$1" -c 10; echo --; cat; } < "$2""#, TEN)?;
+    // pipe, which can't be seeked back
+    run_shared_stdin(r#"cat "$2" | { "$1" -c 10; echo --; cat; }"#, TEN)
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_n2() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-n", "2"])
+        .write_stdin("a\nb\0c\0d\0")
+        .assert()
+        .success()
+        .stdout("a\nb\0c\0");
+
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_unterminated_last() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--zero-terminated"])
+        .write_stdin("a\0b")
+        .assert()
+        .success()
+        .stdout("a\0b");
+
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_non_utf8_name() -> TestResult {
+    // names from `find -print0` needn't be UTF-8, and are passed on as they are
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-n", "1"])
+        .write_stdin(&b"caf\xe9.txt\0b.txt\0"[..])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"caf\xe9.txt\0"[..]));
+
+    Ok(())
+}
+
+#[test]
+fn delimiter_n2() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--delimiter", ",", "-n", "2"])
+        .write_stdin("a,b,c\n")
+        .assert()
+        .success()
+        .stdout("a,b,");
+
+    Ok(())
+}
+
+#[test]
+fn dies_zero_terminated_and_delimiter() -> TestResult {
+    let expected = "the argument '--zero-terminated' cannot be used with '--delimiter <BYTE>'";
+    Command::cargo_bin(PRG)?
+        .args(["-z", "--delimiter", ","])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--delimiter", "ab"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("delimiter must be a single byte"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+const HEADERS: &str = "From: a\nTo: b\n\nbody\n---\nrest\n";
+
+fn run_headers(args: &[&str], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(HEADERS)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn until_blank() -> TestResult {
+    run_headers(&["--until", "^$"], "From: a\nTo: b\n")
+}
+
+#[test]
+fn until_inclusive() -> TestResult {
+    run_headers(
+        &["--until", "^---", "--inclusive"],
+        "From: a\nTo: b\n\nbody\n---\n",
+    )
+}
+
+#[test]
+fn until_bounded_by_lines() -> TestResult {
+    run_headers(&["--until", "^---", "-n", "3"], "From: a\nTo: b\n\n")
+}
+
+#[test]
+fn until_no_match_is_unbounded() -> TestResult {
+    run_headers(&["--until", "^nope$"], HEADERS)
+}
+
+#[test]
+fn while_match() -> TestResult {
+    run_headers(&["--while", ": "], "From: a\nTo: b\n")
+}
+
+#[test]
+fn while_inclusive() -> TestResult {
+    run_headers(&["--while", ": ", "--inclusive"], "From: a\nTo: b\n\n")
+}
+
+#[test]
+fn until_zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "--until", "^b$"])
+        .write_stdin("a\0b\0c\0")
+        .assert()
+        .success()
+        .stdout("a\0");
+
+    Ok(())
+}
+
+#[test]
+fn dies_until_and_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "1", "--until", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_inclusive_alone() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--inclusive"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("required arguments were not provided"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--until", "*"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("regex parse error"));
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn numbers(count: usize) -> String {
+    (1..=count).map(|n| format!("{}\n", n)).collect()
+}
+
+#[test]
+fn peek_n3() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--peek", "-n", "3"])
+        .write_stdin(numbers(100))
+        .assert()
+        .success()
+        .stdout("1\n2\n3\n... (94 lines omitted)\n98\n99\n100\n");
+
+    Ok(())
+}
+
+#[test]
+fn peek_short_file_has_no_gap() -> TestResult {
+    run(&["--peek", TEN], TEN)
+}
+
+#[test]
+fn sample_seeded_is_reproducible() -> TestResult {
+    let args = ["--sample", "5", "--seed", "42"];
+    let first = Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(numbers(1000))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(numbers(1000))
+        .assert()
+        .success()
+        .stdout(predicate::eq(first.clone()));
+
+    // five distinct lines of the input, in their original order
+    let picked = String::from_utf8(first)?
+        .lines()
+        .map(|l| l.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(picked.len(), 5);
+    assert!(picked.windows(2).all(|w| w[0] < w[1]));
+    assert!(picked.iter().all(|n| (1..=1000).contains(n)));
+
+    Ok(())
+}
+
+#[test]
+fn sample_more_than_lines() -> TestResult {
+    run(&["--sample", "20", TEN], TEN)
+}
+
+#[test]
+fn dies_seed_without_sample() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--seed", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--sample <K>"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_peek_and_sample() -> TestResult {
+    let expected = "the argument '--peek' cannot be used with '--sample <K>'";
+    Command::cargo_bin(PRG)?
+        .args(["--peek", "--sample", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+
+    Ok(())
+}
+
+#[test]
+fn bytes_are_not_rewritten() -> TestResult {
+    // an invalid UTF-8 byte goes out as it came in
+    Command::cargo_bin(PRG)?
+        .args(["-c", "5"])
+        .write_stdin(&b"caf\xe9\nmore"[..])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"caf\xe9\n"[..]));
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use wcr::bench::{count_fast, count_full};
+
+const ATLAMAL: &str = include_str!("../tests/inputs/atlamal.txt");
+
+fn lines_and_bytes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lines_and_bytes");
+    for size in [64 * 1024, 4 * 1024 * 1024] {
+        let input = ATLAMAL.repeat(size / ATLAMAL.len() + 1).into_bytes();
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("count", size), &input, |b, input| {
+            b.iter(|| count_full(black_box(input)))
+        });
+        group.bench_with_input(BenchmarkId::new("fast", size), &input, |b, input| {
+            b.iter(|| count_fast(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lines_and_bytes);
+criterion_main!(benches);
@@ -19,9 +19,15 @@ for FILE in $FILES; do
     wc -wl  $FILE > ${OUT_DIR}/${BASENAME}.wl.out
     wc -cl  $FILE > ${OUT_DIR}/${BASENAME}.cl.out
     wc -ml  $FILE > ${OUT_DIR}/${BASENAME}.ml.out
+    wc -L   $FILE > ${OUT_DIR}/${BASENAME}.L.out
+    wc -lL  $FILE > ${OUT_DIR}/${BASENAME}.lL.out
 done
 
-wc < "$ROOT/atlamal.txt" > "$OUT_DIR/atlamal.txt.stdin.out"
+# the tests write stdin through a pipe, which isn't a regular file, so the width is fixed at 7
+cat "$ROOT/atlamal.txt" | wc      > "$OUT_DIR/atlamal.txt.stdin.out"
+cat "$ROOT/atlamal.txt" | wc -l   > "$OUT_DIR/atlamal.txt.stdin.l.out"
+cat "$ROOT/atlamal.txt" | wc -lL  > "$OUT_DIR/atlamal.txt.stdin.lL.out"
+cat "$ROOT/atlamal.txt" | wc - "$ROOT/fox.txt" > "$OUT_DIR/atlamal.txt.stdin.dash.out"
 
 wc      $FILES > $OUT_DIR/all.out
 wc -l   $FILES > $OUT_DIR/all.l.out
@@ -34,3 +40,13 @@ wc -wm  $FILES > $OUT_DIR/all.wm.out
 wc -wl  $FILES > $OUT_DIR/all.wl.out
 wc -cl  $FILES > $OUT_DIR/all.cl.out
 wc -ml  $FILES > $OUT_DIR/all.ml.out
+wc -L   $FILES > $OUT_DIR/all.L.out
+wc -lL  $FILES > $OUT_DIR/all.lL.out
+wc -lwmL $FILES > $OUT_DIR/all.lwmL.out
+wc -lwcL $FILES > $OUT_DIR/all.lwcL.out
+
+# a char that isn't printable neither starts nor ends a word
+wc -w "$ROOT/ctrl.txt" > "$OUT_DIR/ctrl.txt.w.out"
+
+# a directory can be opened but not read, so it still gets a row after the error
+wc "$ROOT" "$ROOT/fox.txt" > "$OUT_DIR/dir.out" 2>/dev/null
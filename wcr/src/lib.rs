@@ -1,10 +1,23 @@
 use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashMap},
+    env,
     error::Error,
-    fs::File,
-    io::{self, BufRead, BufReader},
+    fs::{self, File, Metadata},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    num::NonZeroUsize,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
 };
 
-use clap::{ArgAction, ArgGroup, Parser};
+use clap::{ArgAction, ArgGroup, Parser, ValueEnum};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+use walkdir::WalkDir;
 
 type MyResult<T> = Result<T, Box<dyn Error>>;
 
@@ -26,24 +39,130 @@ pub struct ArgConfig {
     /// print the byte counts
     #[arg(short = 'c', long)]
     bytes: bool,
-    /// print the word counts
+    /// print the word counts; MODE is gnu (whitespace-separated, the default) or unicode
+    /// (UAX #29 word boundaries)
+    #[arg(
+        short,
+        long,
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "gnu"
+    )]
+    words: Option<WordMode>,
+    /// print the counts of user-perceived characters (extended grapheme clusters)
+    #[arg(long)]
+    graphemes: bool,
+    /// print the maximum display width
+    #[arg(short = 'L', long)]
+    max_line_length: bool,
+    /// read input from the files specified by NUL-terminated names in file F;
+    /// if F is - then read names from standard input
+    #[arg(long, value_name = "F", conflicts_with = "files")]
+    files0_from: Option<String>,
+    /// print one record per file (and the total) instead of columns
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// count up to N files at the same time; output stays in command-line order
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+    /// count the files below directory arguments
     #[arg(short, long)]
-    words: bool,
+    recursive: bool,
+    /// print a subtotal per file extension instead of a line per file
+    #[arg(long)]
+    by_ext: bool,
+    /// with -r, only count files whose name matches GLOB
+    #[arg(long, value_name = "GLOB", action = ArgAction::Append, requires = "recursive")]
+    include: Vec<String>,
+    /// with -r, skip files and directories whose name matches GLOB
+    #[arg(long, value_name = "GLOB", action = ArgAction::Append, requires = "recursive")]
+    exclude: Vec<String>,
+    /// print the N most frequent words instead of the counts (every word for 0)
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10",
+        conflicts_with_all = ["format", "by_ext"]
+    )]
+    freq: Option<usize>,
+    /// with --freq, fold words to lowercase
+    #[arg(long, requires = "freq")]
+    ignore_case: bool,
+    /// with --freq, strip punctuation and symbols from both ends of words
+    #[arg(long, requires = "freq")]
+    strip_punct: bool,
+    /// with --freq, leave out words shorter than N chars
+    #[arg(long, value_name = "N", requires = "freq")]
+    min_len: Option<usize>,
+    /// count again every SECS seconds, only reading what was appended since, and print the
+    /// running counts with the change per second of the first one
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = parse_interval,
+        conflicts_with_all = [
+            "files0_from",
+            "format",
+            "freq",
+            "by_ext",
+            "recursive",
+            "jobs"
+        ]
+    )]
+    watch: Option<Duration>,
+    /// with --watch, stop after N passes
+    #[arg(long, value_name = "N", requires = "watch")]
+    passes: Option<NonZeroUsize>,
+}
+
+/// a --watch interval, in (possibly fractional) seconds
+fn parse_interval(secs: &str) -> Result<Duration, String> {
+    match secs.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && 0.0 < secs => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(format!("\"{}\" isn't a positive number of seconds", secs)),
+    }
+}
+
+/// what --words counts as a word
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum WordMode {
+    /// runs of non-whitespace characters, like GNU wc
+    Gnu,
+    /// words between UAX #29 word boundaries, leaving out punctuation and spaces
+    Unicode,
+}
+
+/// machine-readable output formats
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// one JSON object per line
+    Json,
+    /// comma-separated values, with a header
+    Csv,
+    /// tab-separated values, with a header
+    Tsv,
 }
 
 pub fn get_args() -> MyResult<ArgConfig> {
     let mut cfg = ArgConfig::parse();
 
-    if cfg.files.is_empty() {
-        cfg.files = vec!["-".to_string()];
-    }
-
-    if [cfg.lines, cfg.words, cfg.bytes, cfg.chars]
-        .iter()
-        .all(|a| false == *a)
+    if [
+        cfg.lines,
+        cfg.words.is_some(),
+        cfg.bytes,
+        cfg.chars,
+        cfg.graphemes,
+        cfg.max_line_length,
+    ]
+    .iter()
+    .all(|a| !(*a))
     {
         cfg.lines = true;
-        cfg.words = true;
+        cfg.words = Some(WordMode::Gnu);
         cfg.bytes = true;
     }
 
@@ -51,40 +170,637 @@ pub fn get_args() -> MyResult<ArgConfig> {
 }
 
 pub fn run(cfg: ArgConfig) -> MyResult<()> {
+    if let Some(top) = cfg.freq {
+        return run_freq(&cfg, top);
+    }
+    if let Some(interval) = cfg.watch {
+        return run_watch(&cfg, interval);
+    }
+
+    // like GNU wc, the inputs known up front are stat'ed first, so that every row can be
+    // printed as soon as it's counted with the width they share
+    let inputs = Inputs::new(&cfg)?;
+    let width = match (&inputs, cfg.format) {
+        (Inputs::Listed(filenames), None) => Width::of(filenames).get(&cfg),
+        _ => 1,
+    };
+    let mut output = Output::new(&cfg, width);
+    let mut total_file_info = FileInfo::new();
+    let mut file_count = 0;
+    let mut by_ext: Option<BTreeMap<String, FileInfo>> = cfg.by_ext.then(BTreeMap::new);
+
+    for_each_file(&cfg, inputs, |filename, counted| {
+        // as in GNU wc, inputs that couldn't be read still make for a total line
+        file_count += 1;
+        match counted {
+            Err(e) => {
+                eprintln!("can't open file {}: {}", filename, e);
+                output.error(filename, &e.to_string(), &cfg)
+            }
+            Ok(file_info) => {
+                total_file_info.add(&file_info);
+                match &mut by_ext {
+                    Some(by_ext) => {
+                        by_ext
+                            .entry(ext_label(filename))
+                            .or_insert_with(FileInfo::new)
+                            .add(&file_info);
+                        Ok(())
+                    }
+                    None => output.row("file", filename, file_info, &cfg),
+                }
+            }
+        }
+    })?;
+
+    for (label, file_info) in by_ext.into_iter().flatten() {
+        output.row("extension", &label, file_info, &cfg)?;
+    }
+
+    output.finish(&total_file_info, file_count, &cfg)
+}
+
+/// prints the `top` most frequent words of all the files, most frequent first and
+/// alphabetically among the same count
+fn run_freq(cfg: &ArgConfig, top: usize) -> MyResult<()> {
     let mut total_file_info = FileInfo::new();
-    let mut file_infos: Vec<FileInfo> = Vec::new();
+    for_each_file(cfg, Inputs::new(cfg)?, |filename, counted| {
+        match counted {
+            Err(e) => eprintln!("can't open file {}: {}", filename, e),
+            Ok(file_info) => total_file_info.add(&file_info),
+        }
+        Ok(())
+    })?;
+
+    let mut words = total_file_info.word_freq.into_iter().collect::<Vec<_>>();
+    words.sort_unstable_by(|(word1, count1), (word2, count2)| {
+        Reverse(count1).cmp(&Reverse(count2)).then(word1.cmp(word2))
+    });
+    if 0 < top {
+        words.truncate(top);
+    }
+
+    let pad = digits(words.first().map_or(0, |(_, count)| *count)) + 1;
+    for (word, count) in words {
+        println!("{} {}", format_field(&count, pad, true), word);
+    }
+
+    Ok(())
+}
+
+/// counts the files every `interval`, printing a block of rows per pass, until --passes of
+/// them are done (or forever)
+fn run_watch(cfg: &ArgConfig, interval: Duration) -> MyResult<()> {
+    if cfg.files.is_empty() || cfg.files.iter().any(|filename| "-" == filename) {
+        return Err("--watch needs files to read, not stdin".into());
+    }
+
+    let mode = Mode::new(cfg);
+    let mut watched = cfg
+        .files
+        .iter()
+        .map(|filename| Watched::new(filename, mode))
+        .collect::<Vec<_>>();
+    let mut last_total = 0;
+    let mut last_pass: Option<Instant> = None;
+
+    for pass in 1.. {
+        let now = Instant::now();
+        let mut total = FileInfo::new();
+        for file in &mut watched {
+            let before = shown_counts(file.file_info(), cfg)
+                .first()
+                .copied()
+                .unwrap_or(0);
+            if let Err(e) = file.update() {
+                eprintln!("can't open file {}: {}", file.filename, e);
+            }
+            let delta = last_pass.map(|last| {
+                let after = shown_counts(file.file_info(), cfg)[0];
+                rate(before, after, now - last)
+            });
+            print_watched(file.file_info(), &file.filename, delta, cfg);
+            total.add(file.file_info());
+        }
+        let total_count = shown_counts(&total, cfg)[0];
+        if 1 < watched.len() {
+            let delta = last_pass.map(|last| rate(last_total, total_count, now - last));
+            print_watched(&total, "total", delta, cfg);
+        }
+        println!();
+        io::stdout().flush()?;
+
+        if cfg.passes.is_some_and(|passes| passes.get() <= pass) {
+            break;
+        }
+        last_total = total_count;
+        last_pass = Some(now);
+        thread::sleep(interval);
+    }
+
+    Ok(())
+}
+
+/// the change per second from `before` to `after`, which can go down after a truncation
+fn rate(before: usize, after: usize, elapsed: Duration) -> f64 {
+    (after as f64 - before as f64) / elapsed.as_secs_f64()
+}
+
+/// a --watch row: the counts (wide enough for growing files) and the rate of the first one,
+/// which is "-" on the first pass
+fn print_watched(file_info: &FileInfo, filename: &str, delta: Option<f64>, cfg: &ArgConfig) {
+    let delta = match delta {
+        Some(delta) => format!("{:.1}/s", delta),
+        None => "-".to_string(),
+    };
+    println!(
+        "{} {:>10} {}",
+        format_counts(file_info, cfg, 7),
+        delta,
+        filename
+    );
+}
+
+/// how many of the last bytes counted --watch keeps, to tell whether they're still there
+const WATCHED_RECENT: usize = 64;
+
+/// a file followed by --watch, with the counts so far and where they stopped
+#[derive(Debug)]
+struct Watched {
+    filename: String,
+    mode: Mode,
+    /// the device and inode of the file counted, which change when it's rotated
+    id: Option<(u64, u64)>,
+    /// the end of what's been counted
+    offset: u64,
+    /// the last bytes counted, which are different after a truncation that the file has
+    /// since grown back past the offset from
+    recent: Vec<u8>,
+    counter: Counter,
+}
+
+impl Watched {
+    fn new(filename: &str, mode: Mode) -> Self {
+        Self {
+            filename: filename.to_string(),
+            mode,
+            id: None,
+            offset: 0,
+            recent: Vec::new(),
+            counter: Self::counter(mode),
+        }
+    }
+
+    fn counter(mode: Mode) -> Counter {
+        match mode {
+            Mode::Full(decoding) => Counter::with_decoding(decoding),
+            Mode::Lines | Mode::Bytes => Counter::new(),
+        }
+    }
+
+    /// the counts so far; the words and width of a line still being written are only added
+    /// as far as they're known
+    fn file_info(&self) -> &FileInfo {
+        &self.counter.file_info
+    }
+
+    /// counts the bytes appended since the last update. a file that was replaced (rotated) or
+    /// truncated is counted again from the start
+    fn update(&mut self) -> MyResult<()> {
+        let mut file = File::open(&self.filename)?;
+        let metadata = file.metadata()?;
+        let id = file_id(&metadata);
+        let len = metadata.len();
+        if self.id.is_some() && id != self.id {
+            eprintln!("{}: file replaced", self.filename);
+            self.restart();
+        } else if len < self.offset || !self.recent_still_there(&mut file)? {
+            eprintln!("{}: file truncated", self.filename);
+            self.restart();
+        }
+        self.id = id;
+        file.seek(SeekFrom::Start(self.offset))?;
+
+        let mut appended = file.take(len - self.offset);
+        let mut block = vec![0; BLOCK_SIZE];
+        loop {
+            let bread = match appended.read(&mut block) {
+                Ok(0) => break,
+                Ok(bread) => bread,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let chunk = &block[..bread];
+            self.offset += bread as u64;
+            match self.mode {
+                Mode::Full(_) => self.counter.update(chunk),
+                Mode::Lines | Mode::Bytes => {
+                    let file_info = &mut self.counter.file_info;
+                    file_info.byte_count += bread;
+                    file_info.line_count += memchr::memchr_iter(b'\n', chunk).count();
+                }
+            }
+            self.recent.extend_from_slice(chunk);
+            let extra = self.recent.len().saturating_sub(WATCHED_RECENT);
+            self.recent.drain(..extra);
+        }
+
+        Ok(())
+    }
+
+    fn restart(&mut self) {
+        self.offset = 0;
+        self.recent.clear();
+        self.counter = Self::counter(self.mode);
+    }
+
+    /// whether the file still has the last bytes counted where they were
+    fn recent_still_there(&self, file: &mut File) -> io::Result<bool> {
+        let mut read = vec![0; self.recent.len()];
+        file.seek(SeekFrom::Start(self.offset - self.recent.len() as u64))?;
+        file.read_exact(&mut read)?;
+        Ok(read == self.recent)
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// the name of the --by-ext group `filename` goes in
+fn ext_label(filename: &str) -> String {
+    match Path::new(filename).extension() {
+        Some(ext) => format!(".{}", ext.to_string_lossy()),
+        None => "(none)".to_string(),
+    }
+}
+
+/// the files --recursive finds below a directory, with --include/--exclude applied
+struct Walk {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl Walk {
+    fn new(cfg: &ArgConfig) -> MyResult<Option<Self>> {
+        if !cfg.recursive {
+            return Ok(None);
+        }
+
+        let glob_set = |globs: &[String]| -> MyResult<GlobSet> {
+            let mut builder = GlobSetBuilder::new();
+            for glob in globs {
+                builder.add(Glob::new(glob)?);
+            }
+            Ok(builder.build()?)
+        };
+
+        Ok(Some(Self {
+            include: glob_set(&cfg.include)?,
+            exclude: glob_set(&cfg.exclude)?,
+        }))
+    }
+
+    /// the files to count for `filename`: the files below it when it's a directory, in name
+    /// order, or else just itself (so stdin and missing files go through the usual path)
+    fn expand(&self, filename: &str) -> Vec<String> {
+        if !Path::new(filename).is_dir() {
+            return vec![filename.to_string()];
+        }
+
+        let walker = WalkDir::new(filename)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| 0 == entry.depth() || !self.exclude.is_match(entry.file_name()));
 
-    for filename in &cfg.files {
-        match open(filename) {
-            Err(e) => eprintln!("can't open file {}: {}", &filename, e),
-            Ok(buf) => {
-                let file_info = count(buf)?;
-                total_file_info.line_count += file_info.line_count;
-                total_file_info.word_count += file_info.word_count;
-                total_file_info.char_count += file_info.char_count;
-                total_file_info.byte_count += file_info.byte_count;
-                file_infos.push(file_info);
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = match entry {
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+                Ok(entry) => entry,
+            };
+            if !entry.file_type().is_file()
+                || (!self.include.is_empty() && !self.include.is_match(entry.file_name()))
+            {
+                continue;
             }
+            match entry.path().to_str() {
+                Some(path) => files.push(path.to_string()),
+                None => eprintln!("{}: invalid UTF-8 in file name", entry.path().display()),
+            }
+        }
+
+        files
+    }
+}
+
+/// how many names of a --files0-from list are counted together when running with --jobs
+const FILES0_BATCH: usize = 1024;
+
+/// what there is to count
+enum Inputs {
+    /// the files on the command line (or the ones below them with -r), stdin, or the names of
+    /// a --files0-from list that's small enough to be read up front
+    Listed(Vec<String>),
+    /// a --files0-from list that's read as it's counted, since it can be far too long to hold
+    Streamed(String),
+}
+
+impl Inputs {
+    fn new(cfg: &ArgConfig) -> MyResult<Self> {
+        let walk = Walk::new(cfg)?;
+        match &cfg.files0_from {
+            None if cfg.files.is_empty() => Ok(Inputs::Listed(vec!["-".to_string()])),
+            None => Ok(Inputs::Listed(
+                cfg.files
+                    .iter()
+                    .flat_map(|filename| expand(walk.as_ref(), filename))
+                    .collect(),
+            )),
+            Some(list) if is_small_file(list) => {
+                let mut filenames = Vec::new();
+                for_each_name(list, |filename| {
+                    filenames.extend(expand(walk.as_ref(), &filename));
+                    Ok(())
+                })?;
+                Ok(Inputs::Listed(filenames))
+            }
+            Some(list) => Ok(Inputs::Streamed(list.clone())),
         }
     }
+}
+
+/// the files to count for `filename`, which are the ones below it with -r
+fn expand(walk: Option<&Walk>, filename: &str) -> Vec<String> {
+    match walk {
+        Some(walk) => walk.expand(filename),
+        None => vec![filename.to_string()],
+    }
+}
 
-    /* now get the pad and print the file infos */
-    let pad = get_pad(total_file_info.byte_count);
-    for (idx, file_info) in file_infos.iter().enumerate() {
-        print_fileinfo(file_info, &cfg.files[idx], &cfg, pad);
+/// hands the names of the NUL-separated --files0-from `list` to `on_name` as they're read,
+/// skipping (and reporting) the ones that can't be files
+fn for_each_name(list: &str, mut on_name: impl FnMut(String) -> MyResult<()>) -> MyResult<()> {
+    for (idx, name) in open(list)?.split(b'\0').enumerate() {
+        let filename = match String::from_utf8(name?) {
+            Ok(filename) => filename,
+            Err(_) => {
+                eprintln!("{}:{}: invalid UTF-8 in file name", list, idx + 1);
+                continue;
+            }
+        };
+        if filename.is_empty() {
+            eprintln!("{}:{}: invalid zero-length file name", list, idx + 1);
+            continue;
+        }
+        if "-" == list && "-" == filename {
+            eprintln!("when reading file names from standard input, no file name of '-' allowed");
+            continue;
+        }
+        on_name(filename)?;
     }
 
-    if 1 < file_infos.len() {
-        print_fileinfo(&total_file_info, "total", &cfg, pad);
+    Ok(())
+}
+
+/// counts every input and hands each result to `on_file` in order
+fn for_each_file(
+    cfg: &ArgConfig,
+    inputs: Inputs,
+    mut on_file: impl FnMut(&str, MyResult<FileInfo>) -> MyResult<()>,
+) -> MyResult<()> {
+    let mode = Mode::new(cfg);
+    let pool = match cfg.jobs {
+        Some(jobs) => Some(ThreadPoolBuilder::new().num_threads(jobs.get()).build()?),
+        None => None,
+    };
+    let list = match inputs {
+        Inputs::Listed(filenames) => {
+            return count_batch(&filenames, mode, pool.as_ref(), &mut on_file);
+        }
+        Inputs::Streamed(list) => list,
+    };
+
+    // only a batch of names is held at a time, so that the pool has something to work on
+    let walk = Walk::new(cfg)?;
+    let batch_size = if pool.is_some() { FILES0_BATCH } else { 1 };
+    let mut batch = Vec::with_capacity(batch_size);
+    for_each_name(&list, |filename| {
+        batch.extend(expand(walk.as_ref(), &filename));
+        if batch_size <= batch.len() {
+            count_batch(&batch, mode, pool.as_ref(), &mut on_file)?;
+            batch.clear();
+        }
+        Ok(())
+    })?;
+
+    count_batch(&batch, mode, pool.as_ref(), &mut on_file)
+}
+
+/// counts `filenames`, on the pool if there is one, and hands the results to `on_file` in the
+/// order of `filenames`
+fn count_batch(
+    filenames: &[String],
+    mode: Mode,
+    pool: Option<&ThreadPool>,
+    on_file: &mut impl FnMut(&str, MyResult<FileInfo>) -> MyResult<()>,
+) -> MyResult<()> {
+    let count_file = |filename: &str| count_file(filename, mode);
+
+    let Some(pool) = pool else {
+        for filename in filenames {
+            on_file(filename, count_file(filename))?;
+        }
+        return Ok(());
+    };
+
+    // stdin is left for the in-order pass below: only the first "-" gets its contents, as in
+    // the serial path. errors become strings to cross threads
+    let counted: Vec<Option<Result<FileInfo, String>>> = pool.install(|| {
+        filenames
+            .par_iter()
+            .map(|filename| {
+                ("-" != filename).then(|| count_file(filename).map_err(|e| e.to_string()))
+            })
+            .collect()
+    });
+
+    for (filename, counted) in filenames.iter().zip(counted) {
+        let counted = match counted {
+            Some(counted) => counted.map_err(From::from),
+            None => count_file(filename),
+        };
+        on_file(filename, counted)?;
     }
 
     Ok(())
 }
 
+/// where the counts go, as padded columns or as one record per file
+enum Output {
+    /// rows are printed as they come, padded to the width worked out before counting; that's 1
+    /// for a --files0-from list that isn't read up front
+    Columns(usize),
+    Json(io::Stdout),
+    Csv(Box<csv::Writer<io::Stdout>>),
+}
+
+/// a row of the --format output; counts are empty for a file that couldn't be read
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    /// "file", "extension" (with --by-ext), "error" or "total"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    path: Option<&'a str>,
+    lines: Option<usize>,
+    words: Option<usize>,
+    /// how the words were told apart, "gnu" or "unicode"
+    word_mode: Option<&'static str>,
+    chars: Option<usize>,
+    /// only counted with --graphemes
+    graphemes: Option<usize>,
+    bytes: Option<usize>,
+    max_line_length: Option<usize>,
+    error: Option<&'a str>,
+}
+
+impl<'a> Record<'a> {
+    fn counts(
+        kind: &'static str,
+        path: Option<&'a str>,
+        file_info: &FileInfo,
+        cfg: &ArgConfig,
+    ) -> Self {
+        let word_mode = match cfg.words.unwrap_or(WordMode::Gnu) {
+            WordMode::Gnu => "gnu",
+            WordMode::Unicode => "unicode",
+        };
+        Self {
+            kind,
+            path,
+            lines: Some(file_info.line_count),
+            words: Some(file_info.word_count),
+            word_mode: Some(word_mode),
+            chars: Some(file_info.char_count),
+            graphemes: cfg.graphemes.then_some(file_info.grapheme_count),
+            bytes: Some(file_info.byte_count),
+            max_line_length: Some(file_info.max_line_length),
+            error: None,
+        }
+    }
+
+    fn error(path: &'a str, error: &'a str) -> Self {
+        Self {
+            kind: "error",
+            path: Some(path),
+            lines: None,
+            words: None,
+            word_mode: None,
+            chars: None,
+            graphemes: None,
+            bytes: None,
+            max_line_length: None,
+            error: Some(error),
+        }
+    }
+}
+
+impl Output {
+    fn new(cfg: &ArgConfig, width: usize) -> Self {
+        let csv_with = |delimiter| {
+            Box::new(
+                csv::WriterBuilder::new()
+                    .delimiter(delimiter)
+                    .from_writer(io::stdout()),
+            )
+        };
+        match cfg.format {
+            Some(Format::Json) => Output::Json(io::stdout()),
+            Some(Format::Csv) => Output::Csv(csv_with(b',')),
+            Some(Format::Tsv) => Output::Csv(csv_with(b'\t')),
+            None => Output::Columns(width),
+        }
+    }
+
+    /// a row of counts, for a file or a --by-ext group (named by `kind` in records)
+    fn row(
+        &mut self,
+        kind: &'static str,
+        name: &str,
+        file_info: FileInfo,
+        cfg: &ArgConfig,
+    ) -> MyResult<()> {
+        match self {
+            Output::Columns(width) => {
+                print_fileinfo(&file_info, column_name(name, cfg), cfg, *width)
+            }
+            _ => self.record(&Record::counts(kind, Some(name), &file_info, cfg))?,
+        }
+
+        Ok(())
+    }
+
+    /// errors only show up in records, the columns leave them to stderr
+    fn error(&mut self, filename: &str, error: &str, cfg: &ArgConfig) -> MyResult<()> {
+        match self {
+            // GNU wc opens a directory fine and only fails to read it, so it still gets a row
+            Output::Columns(width) if Path::new(filename).is_dir() => {
+                print_fileinfo(&FileInfo::new(), column_name(filename, cfg), cfg, *width);
+                Ok(())
+            }
+            Output::Columns(_) => Ok(()),
+            _ => self.record(&Record::error(filename, error)),
+        }
+    }
+
+    fn finish(self, total: &FileInfo, file_count: usize, cfg: &ArgConfig) -> MyResult<()> {
+        match self {
+            Output::Columns(width) => {
+                if 1 < file_count {
+                    print_fileinfo(total, Some("total"), cfg, width);
+                }
+            }
+            // records always end with the total, so readers don't have to special case one file
+            mut records => {
+                records.record(&Record::counts("total", None, total, cfg))?;
+                if let Output::Csv(mut writer) = records {
+                    writer.flush()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, record: &Record) -> MyResult<()> {
+        match self {
+            Output::Json(out) => {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
+            Output::Csv(writer) => writer.serialize(record)?,
+            Output::Columns(_) => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
 pub fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
     match filename {
         "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(Configfilename)?))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
     }
 }
 
@@ -102,7 +818,12 @@ struct FileInfo {
     line_count: usize,
     word_count: usize,
     char_count: usize,
+    /// only counted with --graphemes
+    grapheme_count: usize,
     byte_count: usize,
+    max_line_length: usize,
+    /// how often each word occurs, only filled in for --freq
+    word_freq: HashMap<String, usize>,
 }
 
 impl FileInfo {
@@ -111,12 +832,64 @@ impl FileInfo {
             line_count: 0,
             word_count: 0,
             char_count: 0,
+            grapheme_count: 0,
             byte_count: 0,
+            max_line_length: 0,
+            word_freq: HashMap::new(),
+        }
+    }
+
+    /// adds `other` into a running total; the max line length of a total is the widest of all
+    fn add(&mut self, other: &FileInfo) {
+        self.line_count += other.line_count;
+        self.word_count += other.word_count;
+        self.char_count += other.char_count;
+        self.grapheme_count += other.grapheme_count;
+        self.byte_count += other.byte_count;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        for (word, count) in &other.word_freq {
+            *self.word_freq.entry(word.clone()).or_insert(0) += count;
+        }
+    }
+}
+
+/// how --freq turns the words `count` finds into the ones it tallies
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FreqOptions {
+    ignore_case: bool,
+    strip_punct: bool,
+    min_len: usize,
+}
+
+impl FreqOptions {
+    fn new(cfg: &ArgConfig) -> Option<Self> {
+        cfg.freq.map(|_| Self {
+            ignore_case: cfg.ignore_case,
+            strip_punct: cfg.strip_punct,
+            min_len: cfg.min_len.unwrap_or(0),
+        })
+    }
+
+    /// the word to tally for `word`, if it's still long enough to count
+    fn key(&self, word: &str) -> Option<String> {
+        let word = if self.strip_punct {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+        } else {
+            word
+        };
+        if word.is_empty() || word.chars().count() < self.min_len {
+            return None;
         }
+
+        Some(if self.ignore_case {
+            word.to_lowercase()
+        } else {
+            word.to_string()
+        })
     }
 }
 
-/**
+/*
  * "mut stream: &TcpStream"  -> stream is mutable, meaning to what TcpStream, stream should point can be
  * changed, but to what TcpStream, stream points to can't be changed
  * stream = (some other TcpStream)  // ok
@@ -126,46 +899,612 @@ impl FileInfo {
  * point to any other TcpStream
  */
 
-// pub fn count(buf: &mut Box<dyn BufRead>) {
-fn count(mut buf: impl BufRead) -> MyResult<FileInfo> {
+/// how bytes are decoded into characters, following the locale like GNU wc does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    /// multi-byte UTF-8; bytes which aren't part of a valid sequence aren't characters
+    Utf8,
+    /// every byte is a character, as in the C/POSIX locale
+    SingleByte,
+}
+
+impl Charset {
+    /// reads LC_ALL, LC_CTYPE and LANG in that order; without any locale set, the locale is
+    /// POSIX "C", where every byte is a character
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|val| !val.is_empty());
+
+        match locale {
+            None => Charset::SingleByte,
+            Some(locale) => {
+                let locale = locale.to_ascii_lowercase();
+                if locale.contains("utf-8") || locale.contains("utf8") {
+                    Charset::Utf8
+                } else {
+                    Charset::SingleByte
+                }
+            }
+        }
+    }
+}
+
+/// how the lines of a full count are decoded and split up
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Decoding {
+    charset: Charset,
+    words: WordMode,
+    graphemes: bool,
+    freq: Option<FreqOptions>,
+}
+
+impl Decoding {
+    /// GNU wc's words, without graphemes or a --freq tally
+    fn plain(charset: Charset) -> Self {
+        Self {
+            charset,
+            words: WordMode::Gnu,
+            graphemes: false,
+            freq: None,
+        }
+    }
+}
+
+/// how much of the input has to be looked at for the requested counts
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// words, chars, graphemes or widths are wanted, so every line is decoded (and its words
+    /// tallied for --freq)
+    Full(Decoding),
+    /// only lines and bytes, which just need the newlines found
+    Lines,
+    /// only bytes, which a regular file's size already tells
+    Bytes,
+}
+
+impl Mode {
+    fn new(cfg: &ArgConfig) -> Self {
+        // records have every count in them
+        let freq = FreqOptions::new(cfg);
+        if cfg.words.is_some()
+            || cfg.chars
+            || cfg.graphemes
+            || cfg.max_line_length
+            || cfg.format.is_some()
+            || freq.is_some()
+        {
+            Mode::Full(Decoding {
+                charset: Charset::from_env(),
+                words: cfg.words.unwrap_or(WordMode::Gnu),
+                graphemes: cfg.graphemes,
+                freq,
+            })
+        } else if cfg.lines {
+            Mode::Lines
+        } else {
+            Mode::Bytes
+        }
+    }
+}
+
+/// the size of the blocks read by the fast paths
+const BLOCK_SIZE: usize = 64 * 1024;
+
+fn count_file(filename: &str, mode: Mode) -> MyResult<FileInfo> {
+    if let (Mode::Bytes, false) = (mode, "-" == filename) {
+        let file = File::open(filename)?;
+        let metadata = file.metadata()?;
+        // files like the ones in /proc claim to be empty, so those still get read
+        if metadata.is_file() && 0 < metadata.len() {
+            let mut file_info = FileInfo::new();
+            file_info.byte_count = metadata.len() as usize;
+            return Ok(file_info);
+        }
+        return count_lines_bytes(file);
+    }
+
+    let buf = open(filename)?;
+    match mode {
+        Mode::Full(decoding) => count(buf, decoding),
+        Mode::Lines | Mode::Bytes => count_lines_bytes(buf),
+    }
+}
+
+/// counts newlines and bytes a block at a time, finding the newlines with memchr's vectorized
+/// search instead of going through the input line by line
+fn count_lines_bytes(mut input: impl Read) -> MyResult<FileInfo> {
     let mut file_info = FileInfo::new();
-    let mut line_buf = String::new();
+    let mut block = vec![0; BLOCK_SIZE];
 
-    // while let Ok(line) = buf.read_line(&mut line_buf) {
     loop {
-        // shouldn't use while, because at EOF also, it returns Ok(0), resulting in infinite
-        // loop
-        let bread = buf.read_line(&mut line_buf)?;
-        if 0 == bread {
-            break;
-        }
+        let bread = match input.read(&mut block) {
+            Ok(0) => break,
+            Ok(bread) => bread,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
         file_info.byte_count += bread;
-        file_info.line_count += 1;
-        file_info.word_count += line_buf.split_whitespace().count();
-        file_info.char_count += line_buf.chars().count();
-        line_buf.clear();
+        file_info.line_count += memchr::memchr_iter(b'\n', &block[..bread]).count();
     }
 
     Ok(file_info)
 }
 
-fn print_fileinfo(fileinfo: &FileInfo, filename: &str, cfg: &ArgConfig, pad: usize) {
-    // println!("{:>8} {:>8} {:>8} {}", fileinfo.line_count, fileinfo.word_count, fileinfo.byte_count, filename);
+/// the counts of one input, as [`Counter::finish`] gives them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// the newlines
+    pub lines: usize,
+    pub words: usize,
+    /// the chars, leaving out bytes that aren't part of a valid char
+    pub chars: usize,
+    /// the extended grapheme clusters, only counted when [`Counter::graphemes`] is on
+    pub graphemes: usize,
+    pub bytes: usize,
+    /// the display width of the widest line
+    pub max_line_length: usize,
+}
 
-    println!(
-        "{}{}{}{}{}",
-        format_field(&fileinfo.line_count, pad - 1, cfg.lines),
-        format_field(&fileinfo.word_count, pad, cfg.words),
-        format_field(&fileinfo.char_count, pad, cfg.chars),
-        format_field(&fileinfo.byte_count, pad, cfg.bytes),
-        if "-" == filename {
-            "".to_string()
+/// counts lines, words, chars and bytes of input fed to it in chunks of any size, the same as
+/// wcr counts a file
+///
+/// ```
+/// use wcr::Counter;
+///
+/// let mut counter = Counter::new();
+/// counter.update(b"hello wo");
+/// counter.update(b"rld\n\xc3");
+/// counter.update(b"\xa9t\xc3\xa9\n");
+/// let counts = counter.finish();
+/// assert_eq!((counts.lines, counts.words, counts.chars, counts.bytes), (2, 3, 16, 18));
+/// ```
+///
+/// words, chars and widths are counted as the bytes come in, so only a bounded state is kept
+/// between chunks: at most 5 bytes of a char split between them, and for unicode words or
+/// graphemes the text of the segments that could still go on
+///
+/// GNU words are runs of non-whitespace chars that start with a printable one, as glibc has
+/// them: unassigned code points and the noncharacters don't start a word, and U+0085 and the
+/// line and paragraph separators don't end one, while the word joiner U+2060 does. `-L` measures
+/// like GNU wc: tabs move to the next multiple of 8, wide (East Asian) chars take 2 columns,
+/// unprintable chars (U+2028 among them) take none, and '\r' or '\f' start the width over.
+/// The other widths come from Unicode's tables rather than glibc's, which differ for a few
+/// chars, like U+00AD taking a column in glibc. Bytes of an invalid UTF-8 sequence are skipped
+/// the way GNU wc skips them: they aren't chars, take no width, and neither start nor end a
+/// word. A 4 to 6 byte sequence for a code point above U+10FFFF, which glibc still decodes,
+/// is one unprintable char instead. With `freq`, the same words are tallied in `word_freq`
+///
+/// unicode words and graphemes are found in the valid UTF-8 of the input, whatever the locale;
+/// invalid bytes are neither, and end the word or grapheme before them, as a newline does
+#[derive(Debug)]
+pub struct Counter {
+    decoding: Decoding,
+    file_info: FileInfo,
+    /// the start of a UTF-8 sequence the last chunk ended in the middle of
+    partial: Vec<u8>,
+    in_word: bool,
+    /// the column the current line has got to
+    pos: usize,
+    /// the GNU word being read, only kept for a --freq tally
+    word: String,
+    /// the unicode word segments that the next chunk could still change
+    word_tail: String,
+    /// the last grapheme, which the next chunk could still add to
+    grapheme_tail: String,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Counter {
+    /// a counter for UTF-8 input and GNU wc's whitespace-separated words
+    pub fn new() -> Self {
+        Self::with_decoding(Decoding::plain(Charset::Utf8))
+    }
+
+    fn with_decoding(decoding: Decoding) -> Self {
+        Self {
+            decoding,
+            file_info: FileInfo::new(),
+            partial: Vec::new(),
+            in_word: false,
+            pos: 0,
+            word: String::new(),
+            word_tail: String::new(),
+            grapheme_tail: String::new(),
+        }
+    }
+
+    /// how bytes are decoded into chars; [`Charset::from_env`] follows the locale like wcr
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.decoding.charset = charset;
+        self
+    }
+
+    /// what counts as a word, GNU wc's whitespace-separated ones by default
+    pub fn words(mut self, words: WordMode) -> Self {
+        self.decoding.words = words;
+        self
+    }
+
+    /// whether to count graphemes, which are left at 0 otherwise
+    pub fn graphemes(mut self, graphemes: bool) -> Self {
+        self.decoding.graphemes = graphemes;
+        self
+    }
+
+    /// counts the next chunk of input, which can end anywhere, even inside a char
+    pub fn update(&mut self, mut chunk: &[u8]) {
+        self.file_info.byte_count += chunk.len();
+        self.file_info.line_count += memchr::memchr_iter(b'\n', chunk).count();
+
+        let segmented = WordMode::Unicode == self.decoding.words || self.decoding.graphemes;
+        if Charset::SingleByte == self.decoding.charset {
+            for &b in chunk {
+                self.add_char(b.is_ascii().then_some(b as char));
+            }
+            if !segmented {
+                return;
+            }
+        }
+
+        // a char split by the last chunk is finished with the first bytes of this one. if
+        // those show it was invalid, the bytes after it can start another split char
+        while !self.partial.is_empty() {
+            while is_partial(&self.partial) && !chunk.is_empty() {
+                self.partial.push(chunk[0]);
+                chunk = &chunk[1..];
+            }
+            if is_partial(&self.partial) {
+                return;
+            }
+            let partial = std::mem::take(&mut self.partial);
+            self.decode(&partial);
+        }
+        self.decode(chunk);
+    }
+
+    /// the counts of everything fed in, including a last line without a newline
+    pub fn finish(self) -> Counts {
+        let file_info = self.finish_file_info();
+        Counts {
+            lines: file_info.line_count,
+            words: file_info.word_count,
+            chars: file_info.char_count,
+            graphemes: file_info.grapheme_count,
+            bytes: file_info.byte_count,
+            max_line_length: file_info.max_line_length,
+        }
+    }
+
+    /// the counts along with the --freq tally
+    fn finish_file_info(mut self) -> FileInfo {
+        // a char cut off by the end of the input is invalid, and the segments end anyway
+        self.segment("", true);
+        if !self.word.is_empty() {
+            tally(
+                self.decoding.freq,
+                &self.word,
+                &mut self.file_info.word_freq,
+            );
+        }
+        self.file_info.max_line_length = self.file_info.max_line_length.max(self.pos);
+        self.file_info
+    }
+
+    /// goes through the valid UTF-8 of `bytes`, holding back a sequence cut off at the end
+    fn decode(&mut self, mut bytes: &[u8]) {
+        while let Some(chunk) = bytes.utf8_chunks().next() {
+            self.text(chunk.valid());
+            bytes = &bytes[chunk.valid().len()..];
+            if is_partial(bytes) {
+                self.partial.extend_from_slice(bytes);
+                return;
+            }
+            let skip = match beyond_unicode(bytes) {
+                Some(len) => {
+                    if Charset::Utf8 == self.decoding.charset {
+                        self.add_char(None);
+                    }
+                    len
+                }
+                None => chunk.invalid().len(),
+            };
+            if 0 < skip {
+                self.segment("", true);
+            }
+            bytes = &bytes[skip..];
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if Charset::Utf8 == self.decoding.charset {
+            text.chars().for_each(|c| self.add_char(Some(c)));
+        }
+        if WordMode::Unicode == self.decoding.words || self.decoding.graphemes {
+            for line in text.split_inclusive('\n') {
+                self.segment(line, line.ends_with('\n'));
+            }
+        }
+    }
+
+    /// counts a char, or a byte the single byte charset has no char for (like a non-ascii
+    /// byte in C)
+    fn add_char(&mut self, c: Option<char>) {
+        let freq = self.decoding.freq;
+        self.file_info.char_count += 1;
+        let Some(c) = c else {
+            if self.in_word && freq.is_some() {
+                self.word.push(char::REPLACEMENT_CHARACTER);
+            }
+            return;
+        };
+        // as in GNU wc, only a printable char starts a word, while other non-space chars
+        // neither start nor end one
+        if WordMode::Gnu == self.decoding.words {
+            if is_separator(c) {
+                if self.in_word && freq.is_some() {
+                    tally(freq, &self.word, &mut self.file_info.word_freq);
+                    self.word.clear();
+                }
+                self.in_word = false;
+            } else {
+                if !self.in_word && is_printable(c) {
+                    self.in_word = true;
+                    self.file_info.word_count += 1;
+                }
+                if self.in_word && freq.is_some() {
+                    self.word.push(c);
+                }
+            }
+        }
+        match c {
+            '\n' | '\r' | '\x0c' => {
+                self.file_info.max_line_length = self.file_info.max_line_length.max(self.pos);
+                self.pos = 0;
+            }
+            '\t' => self.pos += 8 - self.pos % 8,
+            _ if is_printable(c) => self.pos += c.width().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    /// adds `text` to the unicode words and graphemes, and counts the ones that are done:
+    /// all of them at the `end` of a line or before invalid bytes
+    fn segment(&mut self, text: &str, end: bool) {
+        let Decoding {
+            words,
+            graphemes,
+            freq,
+            ..
+        } = self.decoding;
+        if WordMode::Unicode == words {
+            self.word_tail.push_str(text);
+            // whether a segment is split off can depend on the char after the next one (like
+            // the ' in "can't"), so the last two are kept
+            let done = match end {
+                true => self.word_tail.len(),
+                false => self
+                    .word_tail
+                    .split_word_bound_indices()
+                    .nth_back(1)
+                    .map_or(0, |(idx, _)| idx),
+            };
+            for word in self.word_tail[..done].unicode_words() {
+                self.file_info.word_count += 1;
+                tally(freq, word, &mut self.file_info.word_freq);
+            }
+            self.word_tail.drain(..done);
+        }
+        if graphemes {
+            self.grapheme_tail.push_str(text);
+            let done = match end {
+                true => self.grapheme_tail.len(),
+                false => self
+                    .grapheme_tail
+                    .grapheme_indices(true)
+                    .next_back()
+                    .map_or(0, |(idx, _)| idx),
+            };
+            self.file_info.grapheme_count += self.grapheme_tail[..done].graphemes(true).count();
+            self.grapheme_tail.drain(..done);
+        }
+    }
+}
+
+/// whether `bytes` are the start of a UTF-8 sequence (or one glibc decodes above U+10FFFF)
+/// that goes on in the next chunk
+fn is_partial(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_err_and(|e| e.error_len().is_none())
+        || beyond_unicode(bytes).is_some_and(|len| bytes.len() < len)
+}
+
+/// the length of the sequence `bytes` starts with (or the start of) if it's one of the 4 to 6
+/// byte sequences for code points above U+10FFFF, which glibc still decodes as a char
+fn beyond_unicode(bytes: &[u8]) -> Option<usize> {
+    let (len, least) = match bytes.first()? {
+        0xf4 => (4, 0x90),
+        0xf5..=0xf7 => (4, 0x80),
+        0xf8 => (5, 0x88),
+        0xf9..=0xfb => (5, 0x80),
+        0xfc => (6, 0x84),
+        0xfd => (6, 0x80),
+        _ => return None,
+    };
+    let rest = &bytes[1..len.min(bytes.len())];
+    let continues = rest.iter().all(|b| b & 0xc0 == 0x80);
+    (continues && rest.first().is_none_or(|&b| least <= b)).then_some(len)
+}
+
+/// whether glibc's iswprint takes `c` as printable in a UTF-8 locale: everything but control
+/// chars, the line and paragraph separators, and code points nothing is assigned to
+fn is_printable(c: char) -> bool {
+    if c.is_ascii() {
+        return !c.is_ascii_control();
+    }
+    let c = c as u32;
+    !(0x80..0xa0).contains(&c)
+        && !(0x2028..=0x2029).contains(&c)
+        && UNASSIGNED
+            .binary_search_by(|&(first, last)| {
+                if last < c {
+                    Ordering::Less
+                } else if c < first {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_err()
+}
+
+/// whether `c` ends a GNU word: the whitespace glibc has as printable, \t to \r, and the
+/// word joiner, which GNU wc takes as a no-break space along with U+00A0, U+2007 and U+202F
+fn is_separator(c: char) -> bool {
+    matches!(c, '\t'..='\r' | '\u{2060}') || c.is_whitespace() && is_printable(c)
+}
+
+/// adds `word` to the --freq tally, if it's one that gets tallied
+fn tally(freq: Option<FreqOptions>, word: &str, word_freq: &mut HashMap<String, usize>) {
+    if let Some(key) = freq.and_then(|freq| freq.key(word)) {
+        *word_freq.entry(key).or_insert(0) += 1;
+    }
+}
+
+fn count(mut buf: impl BufRead, decoding: Decoding) -> MyResult<FileInfo> {
+    let mut counter = Counter::with_decoding(decoding);
+    loop {
+        let chunk = match buf.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let len = chunk.len();
+        counter.update(chunk);
+        buf.consume(len);
+    }
+
+    Ok(counter.finish_file_info())
+}
+
+/// the width GNU wc gives the columns: the digits of the total size of the inputs, or at
+/// least 7 when one of them isn't a regular file and its size can't be known up front
+#[derive(Debug, Default)]
+struct Width {
+    inputs: usize,
+    regular_total: u64,
+    irregular: bool,
+}
+
+impl Width {
+    /// stats every input; the ones that can't be stat'ed still count as inputs, but not
+    /// towards the width
+    fn of(filenames: &[String]) -> Self {
+        let mut width = Width::default();
+        for filename in filenames {
+            width.inputs += 1;
+            match metadata(filename) {
+                Ok(metadata) if metadata.is_file() => width.regular_total += metadata.len(),
+                Ok(_) => width.irregular = true,
+                Err(_) => {}
+            }
+        }
+        width
+    }
+
+    fn get(&self, cfg: &ArgConfig) -> usize {
+        let shown = [
+            cfg.lines,
+            cfg.words.is_some(),
+            cfg.chars,
+            cfg.graphemes,
+            cfg.bytes,
+            cfg.max_line_length,
+        ];
+        // a single count of a single input has nothing to line up with
+        if 1 == self.inputs && 1 == shown.iter().filter(|show| **show).count() {
+            return 1;
+        }
+
+        let width = digits(self.regular_total.try_into().unwrap_or(usize::MAX));
+        if self.irregular {
+            width.max(7)
         } else {
-            format!(" {}", filename)
+            width
         }
-    );
-    // res += format_field(&fileinfo.line_count, &pad, cfg.lines);
-    // println!("{:>pad$} {:>pad$} {:>pad$} {}", fileinfo.line_count, fileinfo.word_count, fileinfo.byte_count, filename);
+    }
+}
+
+fn metadata(filename: &str) -> io::Result<Metadata> {
+    match filename {
+        "-" => stdin_metadata(),
+        _ => fs::metadata(filename),
+    }
+}
+
+#[cfg(unix)]
+fn stdin_metadata() -> io::Result<Metadata> {
+    use std::os::fd::AsFd;
+
+    File::from(io::stdin().as_fd().try_clone_to_owned()?).metadata()
+}
+
+#[cfg(not(unix))]
+fn stdin_metadata() -> io::Result<Metadata> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// GNU wc reads a --files0-from list up front, and so lines up its rows, when the list is a
+/// regular file of at most this many bytes
+const FILES0_MAX_HELD: u64 = 10 * 1024 * 1024;
+
+fn is_small_file(filename: &str) -> bool {
+    metadata(filename).is_ok_and(|metadata| metadata.is_file() && metadata.len() <= FILES0_MAX_HELD)
+}
+
+/// the name shown after the counts, which stdin only gets when it was asked for as "-"
+fn column_name<'a>(filename: &'a str, cfg: &ArgConfig) -> Option<&'a str> {
+    let unnamed = "-" == filename && cfg.files.is_empty() && cfg.files0_from.is_none();
+    (!unnamed).then_some(filename)
+}
+
+/// prints the counts in wc's order, right-aligned to `width` and separated by a space
+fn print_fileinfo(fileinfo: &FileInfo, filename: Option<&str>, cfg: &ArgConfig, width: usize) {
+    let fields = format_counts(fileinfo, cfg, width);
+    match filename {
+        Some(filename) => println!("{} {}", fields, filename),
+        None => println!("{}", fields),
+    }
+}
+
+/// the counts asked for, in wc's order
+fn shown_counts(fileinfo: &FileInfo, cfg: &ArgConfig) -> Vec<usize> {
+    [
+        (cfg.lines, fileinfo.line_count),
+        (cfg.words.is_some(), fileinfo.word_count),
+        (cfg.chars, fileinfo.char_count),
+        (cfg.graphemes, fileinfo.grapheme_count),
+        (cfg.bytes, fileinfo.byte_count),
+        (cfg.max_line_length, fileinfo.max_line_length),
+    ]
+    .iter()
+    .filter(|(show, _)| *show)
+    .map(|(_, value)| *value)
+    .collect()
+}
+
+fn format_counts(fileinfo: &FileInfo, cfg: &ArgConfig, width: usize) -> String {
+    shown_counts(fileinfo, cfg)
+        .iter()
+        .map(|value| format_field(value, width, true))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 fn format_field(value: &usize, pad: usize, show: bool) -> String {
@@ -176,29 +1515,215 @@ fn format_field(value: &usize, pad: usize, show: bool) -> String {
     }
 }
 
-fn get_pad(bc: usize) -> usize {
-    if 0 == bc {
-        2
-    } else {
-        let mut temp = bc;
-        let mut pad = 0;
-        while temp > 0 {
-            temp /= 10;
-            pad += 1;
-        }
-        pad + 1
+/// the number of decimal digits in `n`, which is 1 for 0
+fn digits(n: usize) -> usize {
+    let mut temp = n;
+    let mut digits = 1;
+    while temp >= 10 {
+        temp /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+/// the counting paths, for the benchmarks in benches/; not a stable api
+#[doc(hidden)]
+pub mod bench {
+    use super::{count, count_lines_bytes, Charset, Decoding};
+
+    /// the line by line count, which every mode went through before the fast path, as
+    /// (lines, bytes)
+    pub fn count_full(input: &[u8]) -> (usize, usize) {
+        let file_info = count(input, Decoding::plain(Charset::Utf8)).unwrap();
+        (file_info.line_count, file_info.byte_count)
+    }
+
+    /// the block and memchr based count used for -l and -c, as (lines, bytes)
+    pub fn count_fast(input: &[u8]) -> (usize, usize) {
+        let file_info = count_lines_bytes(input).unwrap();
+        (file_info.line_count, file_info.byte_count)
     }
 }
 
+/// the code points Unicode 14.0, the version glibc 2.36 follows, leaves unassigned, as
+/// sorted ranges
+#[rustfmt::skip]
+const UNASSIGNED: &[(u32, u32)] = &[
+    (0x378, 0x379), (0x380, 0x383), (0x38b, 0x38b), (0x38d, 0x38d), (0x3a2, 0x3a2),
+    (0x530, 0x530), (0x557, 0x558), (0x58b, 0x58c), (0x590, 0x590), (0x5c8, 0x5cf),
+    (0x5eb, 0x5ee), (0x5f5, 0x5ff), (0x70e, 0x70e), (0x74b, 0x74c), (0x7b2, 0x7bf),
+    (0x7fb, 0x7fc), (0x82e, 0x82f), (0x83f, 0x83f), (0x85c, 0x85d), (0x85f, 0x85f),
+    (0x86b, 0x86f), (0x88f, 0x88f), (0x892, 0x897), (0x984, 0x984), (0x98d, 0x98e),
+    (0x991, 0x992), (0x9a9, 0x9a9), (0x9b1, 0x9b1), (0x9b3, 0x9b5), (0x9ba, 0x9bb),
+    (0x9c5, 0x9c6), (0x9c9, 0x9ca), (0x9cf, 0x9d6), (0x9d8, 0x9db), (0x9de, 0x9de),
+    (0x9e4, 0x9e5), (0x9ff, 0xa00), (0xa04, 0xa04), (0xa0b, 0xa0e), (0xa11, 0xa12),
+    (0xa29, 0xa29), (0xa31, 0xa31), (0xa34, 0xa34), (0xa37, 0xa37), (0xa3a, 0xa3b),
+    (0xa3d, 0xa3d), (0xa43, 0xa46), (0xa49, 0xa4a), (0xa4e, 0xa50), (0xa52, 0xa58),
+    (0xa5d, 0xa5d), (0xa5f, 0xa65), (0xa77, 0xa80), (0xa84, 0xa84), (0xa8e, 0xa8e),
+    (0xa92, 0xa92), (0xaa9, 0xaa9), (0xab1, 0xab1), (0xab4, 0xab4), (0xaba, 0xabb),
+    (0xac6, 0xac6), (0xaca, 0xaca), (0xace, 0xacf), (0xad1, 0xadf), (0xae4, 0xae5),
+    (0xaf2, 0xaf8), (0xb00, 0xb00), (0xb04, 0xb04), (0xb0d, 0xb0e), (0xb11, 0xb12),
+    (0xb29, 0xb29), (0xb31, 0xb31), (0xb34, 0xb34), (0xb3a, 0xb3b), (0xb45, 0xb46),
+    (0xb49, 0xb4a), (0xb4e, 0xb54), (0xb58, 0xb5b), (0xb5e, 0xb5e), (0xb64, 0xb65),
+    (0xb78, 0xb81), (0xb84, 0xb84), (0xb8b, 0xb8d), (0xb91, 0xb91), (0xb96, 0xb98),
+    (0xb9b, 0xb9b), (0xb9d, 0xb9d), (0xba0, 0xba2), (0xba5, 0xba7), (0xbab, 0xbad),
+    (0xbba, 0xbbd), (0xbc3, 0xbc5), (0xbc9, 0xbc9), (0xbce, 0xbcf), (0xbd1, 0xbd6),
+    (0xbd8, 0xbe5), (0xbfb, 0xbff), (0xc0d, 0xc0d), (0xc11, 0xc11), (0xc29, 0xc29),
+    (0xc3a, 0xc3b), (0xc45, 0xc45), (0xc49, 0xc49), (0xc4e, 0xc54), (0xc57, 0xc57),
+    (0xc5b, 0xc5c), (0xc5e, 0xc5f), (0xc64, 0xc65), (0xc70, 0xc76), (0xc8d, 0xc8d),
+    (0xc91, 0xc91), (0xca9, 0xca9), (0xcb4, 0xcb4), (0xcba, 0xcbb), (0xcc5, 0xcc5),
+    (0xcc9, 0xcc9), (0xcce, 0xcd4), (0xcd7, 0xcdc), (0xcdf, 0xcdf), (0xce4, 0xce5),
+    (0xcf0, 0xcf0), (0xcf3, 0xcff), (0xd0d, 0xd0d), (0xd11, 0xd11), (0xd45, 0xd45),
+    (0xd49, 0xd49), (0xd50, 0xd53), (0xd64, 0xd65), (0xd80, 0xd80), (0xd84, 0xd84),
+    (0xd97, 0xd99), (0xdb2, 0xdb2), (0xdbc, 0xdbc), (0xdbe, 0xdbf), (0xdc7, 0xdc9),
+    (0xdcb, 0xdce), (0xdd5, 0xdd5), (0xdd7, 0xdd7), (0xde0, 0xde5), (0xdf0, 0xdf1),
+    (0xdf5, 0xe00), (0xe3b, 0xe3e), (0xe5c, 0xe80), (0xe83, 0xe83), (0xe85, 0xe85),
+    (0xe8b, 0xe8b), (0xea4, 0xea4), (0xea6, 0xea6), (0xebe, 0xebf), (0xec5, 0xec5),
+    (0xec7, 0xec7), (0xece, 0xecf), (0xeda, 0xedb), (0xee0, 0xeff), (0xf48, 0xf48),
+    (0xf6d, 0xf70), (0xf98, 0xf98), (0xfbd, 0xfbd), (0xfcd, 0xfcd), (0xfdb, 0xfff),
+    (0x10c6, 0x10c6), (0x10c8, 0x10cc), (0x10ce, 0x10cf), (0x1249, 0x1249), (0x124e, 0x124f),
+    (0x1257, 0x1257), (0x1259, 0x1259), (0x125e, 0x125f), (0x1289, 0x1289), (0x128e, 0x128f),
+    (0x12b1, 0x12b1), (0x12b6, 0x12b7), (0x12bf, 0x12bf), (0x12c1, 0x12c1), (0x12c6, 0x12c7),
+    (0x12d7, 0x12d7), (0x1311, 0x1311), (0x1316, 0x1317), (0x135b, 0x135c), (0x137d, 0x137f),
+    (0x139a, 0x139f), (0x13f6, 0x13f7), (0x13fe, 0x13ff), (0x169d, 0x169f), (0x16f9, 0x16ff),
+    (0x1716, 0x171e), (0x1737, 0x173f), (0x1754, 0x175f), (0x176d, 0x176d), (0x1771, 0x1771),
+    (0x1774, 0x177f), (0x17de, 0x17df), (0x17ea, 0x17ef), (0x17fa, 0x17ff), (0x181a, 0x181f),
+    (0x1879, 0x187f), (0x18ab, 0x18af), (0x18f6, 0x18ff), (0x191f, 0x191f), (0x192c, 0x192f),
+    (0x193c, 0x193f), (0x1941, 0x1943), (0x196e, 0x196f), (0x1975, 0x197f), (0x19ac, 0x19af),
+    (0x19ca, 0x19cf), (0x19db, 0x19dd), (0x1a1c, 0x1a1d), (0x1a5f, 0x1a5f), (0x1a7d, 0x1a7e),
+    (0x1a8a, 0x1a8f), (0x1a9a, 0x1a9f), (0x1aae, 0x1aaf), (0x1acf, 0x1aff), (0x1b4d, 0x1b4f),
+    (0x1b7f, 0x1b7f), (0x1bf4, 0x1bfb), (0x1c38, 0x1c3a), (0x1c4a, 0x1c4c), (0x1c89, 0x1c8f),
+    (0x1cbb, 0x1cbc), (0x1cc8, 0x1ccf), (0x1cfb, 0x1cff), (0x1f16, 0x1f17), (0x1f1e, 0x1f1f),
+    (0x1f46, 0x1f47), (0x1f4e, 0x1f4f), (0x1f58, 0x1f58), (0x1f5a, 0x1f5a), (0x1f5c, 0x1f5c),
+    (0x1f5e, 0x1f5e), (0x1f7e, 0x1f7f), (0x1fb5, 0x1fb5), (0x1fc5, 0x1fc5), (0x1fd4, 0x1fd5),
+    (0x1fdc, 0x1fdc), (0x1ff0, 0x1ff1), (0x1ff5, 0x1ff5), (0x1fff, 0x1fff), (0x2065, 0x2065),
+    (0x2072, 0x2073), (0x208f, 0x208f), (0x209d, 0x209f), (0x20c1, 0x20cf), (0x20f1, 0x20ff),
+    (0x218c, 0x218f), (0x2427, 0x243f), (0x244b, 0x245f), (0x2b74, 0x2b75), (0x2b96, 0x2b96),
+    (0x2cf4, 0x2cf8), (0x2d26, 0x2d26), (0x2d28, 0x2d2c), (0x2d2e, 0x2d2f), (0x2d68, 0x2d6e),
+    (0x2d71, 0x2d7e), (0x2d97, 0x2d9f), (0x2da7, 0x2da7), (0x2daf, 0x2daf), (0x2db7, 0x2db7),
+    (0x2dbf, 0x2dbf), (0x2dc7, 0x2dc7), (0x2dcf, 0x2dcf), (0x2dd7, 0x2dd7), (0x2ddf, 0x2ddf),
+    (0x2e5e, 0x2e7f), (0x2e9a, 0x2e9a), (0x2ef4, 0x2eff), (0x2fd6, 0x2fef), (0x2ffc, 0x2fff),
+    (0x3040, 0x3040), (0x3097, 0x3098), (0x3100, 0x3104), (0x3130, 0x3130), (0x318f, 0x318f),
+    (0x31e4, 0x31ef), (0x321f, 0x321f), (0xa48d, 0xa48f), (0xa4c7, 0xa4cf), (0xa62c, 0xa63f),
+    (0xa6f8, 0xa6ff), (0xa7cb, 0xa7cf), (0xa7d2, 0xa7d2), (0xa7d4, 0xa7d4), (0xa7da, 0xa7f1),
+    (0xa82d, 0xa82f), (0xa83a, 0xa83f), (0xa878, 0xa87f), (0xa8c6, 0xa8cd), (0xa8da, 0xa8df),
+    (0xa954, 0xa95e), (0xa97d, 0xa97f), (0xa9ce, 0xa9ce), (0xa9da, 0xa9dd), (0xa9ff, 0xa9ff),
+    (0xaa37, 0xaa3f), (0xaa4e, 0xaa4f), (0xaa5a, 0xaa5b), (0xaac3, 0xaada), (0xaaf7, 0xab00),
+    (0xab07, 0xab08), (0xab0f, 0xab10), (0xab17, 0xab1f), (0xab27, 0xab27), (0xab2f, 0xab2f),
+    (0xab6c, 0xab6f), (0xabee, 0xabef), (0xabfa, 0xabff), (0xd7a4, 0xd7af), (0xd7c7, 0xd7ca),
+    (0xd7fc, 0xd7ff), (0xfa6e, 0xfa6f), (0xfada, 0xfaff), (0xfb07, 0xfb12), (0xfb18, 0xfb1c),
+    (0xfb37, 0xfb37), (0xfb3d, 0xfb3d), (0xfb3f, 0xfb3f), (0xfb42, 0xfb42), (0xfb45, 0xfb45),
+    (0xfbc3, 0xfbd2), (0xfd90, 0xfd91), (0xfdc8, 0xfdce), (0xfdd0, 0xfdef), (0xfe1a, 0xfe1f),
+    (0xfe53, 0xfe53), (0xfe67, 0xfe67), (0xfe6c, 0xfe6f), (0xfe75, 0xfe75), (0xfefd, 0xfefe),
+    (0xff00, 0xff00), (0xffbf, 0xffc1), (0xffc8, 0xffc9), (0xffd0, 0xffd1), (0xffd8, 0xffd9),
+    (0xffdd, 0xffdf), (0xffe7, 0xffe7), (0xffef, 0xfff8), (0xfffe, 0xffff), (0x1000c, 0x1000c),
+    (0x10027, 0x10027), (0x1003b, 0x1003b), (0x1003e, 0x1003e), (0x1004e, 0x1004f),
+    (0x1005e, 0x1007f), (0x100fb, 0x100ff), (0x10103, 0x10106), (0x10134, 0x10136),
+    (0x1018f, 0x1018f), (0x1019d, 0x1019f), (0x101a1, 0x101cf), (0x101fe, 0x1027f),
+    (0x1029d, 0x1029f), (0x102d1, 0x102df), (0x102fc, 0x102ff), (0x10324, 0x1032c),
+    (0x1034b, 0x1034f), (0x1037b, 0x1037f), (0x1039e, 0x1039e), (0x103c4, 0x103c7),
+    (0x103d6, 0x103ff), (0x1049e, 0x1049f), (0x104aa, 0x104af), (0x104d4, 0x104d7),
+    (0x104fc, 0x104ff), (0x10528, 0x1052f), (0x10564, 0x1056e), (0x1057b, 0x1057b),
+    (0x1058b, 0x1058b), (0x10593, 0x10593), (0x10596, 0x10596), (0x105a2, 0x105a2),
+    (0x105b2, 0x105b2), (0x105ba, 0x105ba), (0x105bd, 0x105ff), (0x10737, 0x1073f),
+    (0x10756, 0x1075f), (0x10768, 0x1077f), (0x10786, 0x10786), (0x107b1, 0x107b1),
+    (0x107bb, 0x107ff), (0x10806, 0x10807), (0x10809, 0x10809), (0x10836, 0x10836),
+    (0x10839, 0x1083b), (0x1083d, 0x1083e), (0x10856, 0x10856), (0x1089f, 0x108a6),
+    (0x108b0, 0x108df), (0x108f3, 0x108f3), (0x108f6, 0x108fa), (0x1091c, 0x1091e),
+    (0x1093a, 0x1093e), (0x10940, 0x1097f), (0x109b8, 0x109bb), (0x109d0, 0x109d1),
+    (0x10a04, 0x10a04), (0x10a07, 0x10a0b), (0x10a14, 0x10a14), (0x10a18, 0x10a18),
+    (0x10a36, 0x10a37), (0x10a3b, 0x10a3e), (0x10a49, 0x10a4f), (0x10a59, 0x10a5f),
+    (0x10aa0, 0x10abf), (0x10ae7, 0x10aea), (0x10af7, 0x10aff), (0x10b36, 0x10b38),
+    (0x10b56, 0x10b57), (0x10b73, 0x10b77), (0x10b92, 0x10b98), (0x10b9d, 0x10ba8),
+    (0x10bb0, 0x10bff), (0x10c49, 0x10c7f), (0x10cb3, 0x10cbf), (0x10cf3, 0x10cf9),
+    (0x10d28, 0x10d2f), (0x10d3a, 0x10e5f), (0x10e7f, 0x10e7f), (0x10eaa, 0x10eaa),
+    (0x10eae, 0x10eaf), (0x10eb2, 0x10eff), (0x10f28, 0x10f2f), (0x10f5a, 0x10f6f),
+    (0x10f8a, 0x10faf), (0x10fcc, 0x10fdf), (0x10ff7, 0x10fff), (0x1104e, 0x11051),
+    (0x11076, 0x1107e), (0x110c3, 0x110cc), (0x110ce, 0x110cf), (0x110e9, 0x110ef),
+    (0x110fa, 0x110ff), (0x11135, 0x11135), (0x11148, 0x1114f), (0x11177, 0x1117f),
+    (0x111e0, 0x111e0), (0x111f5, 0x111ff), (0x11212, 0x11212), (0x1123f, 0x1127f),
+    (0x11287, 0x11287), (0x11289, 0x11289), (0x1128e, 0x1128e), (0x1129e, 0x1129e),
+    (0x112aa, 0x112af), (0x112eb, 0x112ef), (0x112fa, 0x112ff), (0x11304, 0x11304),
+    (0x1130d, 0x1130e), (0x11311, 0x11312), (0x11329, 0x11329), (0x11331, 0x11331),
+    (0x11334, 0x11334), (0x1133a, 0x1133a), (0x11345, 0x11346), (0x11349, 0x1134a),
+    (0x1134e, 0x1134f), (0x11351, 0x11356), (0x11358, 0x1135c), (0x11364, 0x11365),
+    (0x1136d, 0x1136f), (0x11375, 0x113ff), (0x1145c, 0x1145c), (0x11462, 0x1147f),
+    (0x114c8, 0x114cf), (0x114da, 0x1157f), (0x115b6, 0x115b7), (0x115de, 0x115ff),
+    (0x11645, 0x1164f), (0x1165a, 0x1165f), (0x1166d, 0x1167f), (0x116ba, 0x116bf),
+    (0x116ca, 0x116ff), (0x1171b, 0x1171c), (0x1172c, 0x1172f), (0x11747, 0x117ff),
+    (0x1183c, 0x1189f), (0x118f3, 0x118fe), (0x11907, 0x11908), (0x1190a, 0x1190b),
+    (0x11914, 0x11914), (0x11917, 0x11917), (0x11936, 0x11936), (0x11939, 0x1193a),
+    (0x11947, 0x1194f), (0x1195a, 0x1199f), (0x119a8, 0x119a9), (0x119d8, 0x119d9),
+    (0x119e5, 0x119ff), (0x11a48, 0x11a4f), (0x11aa3, 0x11aaf), (0x11af9, 0x11bff),
+    (0x11c09, 0x11c09), (0x11c37, 0x11c37), (0x11c46, 0x11c4f), (0x11c6d, 0x11c6f),
+    (0x11c90, 0x11c91), (0x11ca8, 0x11ca8), (0x11cb7, 0x11cff), (0x11d07, 0x11d07),
+    (0x11d0a, 0x11d0a), (0x11d37, 0x11d39), (0x11d3b, 0x11d3b), (0x11d3e, 0x11d3e),
+    (0x11d48, 0x11d4f), (0x11d5a, 0x11d5f), (0x11d66, 0x11d66), (0x11d69, 0x11d69),
+    (0x11d8f, 0x11d8f), (0x11d92, 0x11d92), (0x11d99, 0x11d9f), (0x11daa, 0x11edf),
+    (0x11ef9, 0x11faf), (0x11fb1, 0x11fbf), (0x11ff2, 0x11ffe), (0x1239a, 0x123ff),
+    (0x1246f, 0x1246f), (0x12475, 0x1247f), (0x12544, 0x12f8f), (0x12ff3, 0x12fff),
+    (0x1342f, 0x1342f), (0x13439, 0x143ff), (0x14647, 0x167ff), (0x16a39, 0x16a3f),
+    (0x16a5f, 0x16a5f), (0x16a6a, 0x16a6d), (0x16abf, 0x16abf), (0x16aca, 0x16acf),
+    (0x16aee, 0x16aef), (0x16af6, 0x16aff), (0x16b46, 0x16b4f), (0x16b5a, 0x16b5a),
+    (0x16b62, 0x16b62), (0x16b78, 0x16b7c), (0x16b90, 0x16e3f), (0x16e9b, 0x16eff),
+    (0x16f4b, 0x16f4e), (0x16f88, 0x16f8e), (0x16fa0, 0x16fdf), (0x16fe5, 0x16fef),
+    (0x16ff2, 0x16fff), (0x187f8, 0x187ff), (0x18cd6, 0x18cff), (0x18d09, 0x1afef),
+    (0x1aff4, 0x1aff4), (0x1affc, 0x1affc), (0x1afff, 0x1afff), (0x1b123, 0x1b14f),
+    (0x1b153, 0x1b163), (0x1b168, 0x1b16f), (0x1b2fc, 0x1bbff), (0x1bc6b, 0x1bc6f),
+    (0x1bc7d, 0x1bc7f), (0x1bc89, 0x1bc8f), (0x1bc9a, 0x1bc9b), (0x1bca4, 0x1ceff),
+    (0x1cf2e, 0x1cf2f), (0x1cf47, 0x1cf4f), (0x1cfc4, 0x1cfff), (0x1d0f6, 0x1d0ff),
+    (0x1d127, 0x1d128), (0x1d1eb, 0x1d1ff), (0x1d246, 0x1d2df), (0x1d2f4, 0x1d2ff),
+    (0x1d357, 0x1d35f), (0x1d379, 0x1d3ff), (0x1d455, 0x1d455), (0x1d49d, 0x1d49d),
+    (0x1d4a0, 0x1d4a1), (0x1d4a3, 0x1d4a4), (0x1d4a7, 0x1d4a8), (0x1d4ad, 0x1d4ad),
+    (0x1d4ba, 0x1d4ba), (0x1d4bc, 0x1d4bc), (0x1d4c4, 0x1d4c4), (0x1d506, 0x1d506),
+    (0x1d50b, 0x1d50c), (0x1d515, 0x1d515), (0x1d51d, 0x1d51d), (0x1d53a, 0x1d53a),
+    (0x1d53f, 0x1d53f), (0x1d545, 0x1d545), (0x1d547, 0x1d549), (0x1d551, 0x1d551),
+    (0x1d6a6, 0x1d6a7), (0x1d7cc, 0x1d7cd), (0x1da8c, 0x1da9a), (0x1daa0, 0x1daa0),
+    (0x1dab0, 0x1deff), (0x1df1f, 0x1dfff), (0x1e007, 0x1e007), (0x1e019, 0x1e01a),
+    (0x1e022, 0x1e022), (0x1e025, 0x1e025), (0x1e02b, 0x1e0ff), (0x1e12d, 0x1e12f),
+    (0x1e13e, 0x1e13f), (0x1e14a, 0x1e14d), (0x1e150, 0x1e28f), (0x1e2af, 0x1e2bf),
+    (0x1e2fa, 0x1e2fe), (0x1e300, 0x1e7df), (0x1e7e7, 0x1e7e7), (0x1e7ec, 0x1e7ec),
+    (0x1e7ef, 0x1e7ef), (0x1e7ff, 0x1e7ff), (0x1e8c5, 0x1e8c6), (0x1e8d7, 0x1e8ff),
+    (0x1e94c, 0x1e94f), (0x1e95a, 0x1e95d), (0x1e960, 0x1ec70), (0x1ecb5, 0x1ed00),
+    (0x1ed3e, 0x1edff), (0x1ee04, 0x1ee04), (0x1ee20, 0x1ee20), (0x1ee23, 0x1ee23),
+    (0x1ee25, 0x1ee26), (0x1ee28, 0x1ee28), (0x1ee33, 0x1ee33), (0x1ee38, 0x1ee38),
+    (0x1ee3a, 0x1ee3a), (0x1ee3c, 0x1ee41), (0x1ee43, 0x1ee46), (0x1ee48, 0x1ee48),
+    (0x1ee4a, 0x1ee4a), (0x1ee4c, 0x1ee4c), (0x1ee50, 0x1ee50), (0x1ee53, 0x1ee53),
+    (0x1ee55, 0x1ee56), (0x1ee58, 0x1ee58), (0x1ee5a, 0x1ee5a), (0x1ee5c, 0x1ee5c),
+    (0x1ee5e, 0x1ee5e), (0x1ee60, 0x1ee60), (0x1ee63, 0x1ee63), (0x1ee65, 0x1ee66),
+    (0x1ee6b, 0x1ee6b), (0x1ee73, 0x1ee73), (0x1ee78, 0x1ee78), (0x1ee7d, 0x1ee7d),
+    (0x1ee7f, 0x1ee7f), (0x1ee8a, 0x1ee8a), (0x1ee9c, 0x1eea0), (0x1eea4, 0x1eea4),
+    (0x1eeaa, 0x1eeaa), (0x1eebc, 0x1eeef), (0x1eef2, 0x1efff), (0x1f02c, 0x1f02f),
+    (0x1f094, 0x1f09f), (0x1f0af, 0x1f0b0), (0x1f0c0, 0x1f0c0), (0x1f0d0, 0x1f0d0),
+    (0x1f0f6, 0x1f0ff), (0x1f1ae, 0x1f1e5), (0x1f203, 0x1f20f), (0x1f23c, 0x1f23f),
+    (0x1f249, 0x1f24f), (0x1f252, 0x1f25f), (0x1f266, 0x1f2ff), (0x1f6d8, 0x1f6dc),
+    (0x1f6ed, 0x1f6ef), (0x1f6fd, 0x1f6ff), (0x1f774, 0x1f77f), (0x1f7d9, 0x1f7df),
+    (0x1f7ec, 0x1f7ef), (0x1f7f1, 0x1f7ff), (0x1f80c, 0x1f80f), (0x1f848, 0x1f84f),
+    (0x1f85a, 0x1f85f), (0x1f888, 0x1f88f), (0x1f8ae, 0x1f8af), (0x1f8b2, 0x1f8ff),
+    (0x1fa54, 0x1fa5f), (0x1fa6e, 0x1fa6f), (0x1fa75, 0x1fa77), (0x1fa7d, 0x1fa7f),
+    (0x1fa87, 0x1fa8f), (0x1faad, 0x1faaf), (0x1fabb, 0x1fabf), (0x1fac6, 0x1facf),
+    (0x1fada, 0x1fadf), (0x1fae8, 0x1faef), (0x1faf7, 0x1faff), (0x1fb93, 0x1fb93),
+    (0x1fbcb, 0x1fbef), (0x1fbfa, 0x1ffff), (0x2a6e0, 0x2a6ff), (0x2b739, 0x2b73f),
+    (0x2b81e, 0x2b81f), (0x2cea2, 0x2ceaf), (0x2ebe1, 0x2f7ff), (0x2fa1e, 0x2ffff),
+    (0x3134b, 0xe0000), (0xe0002, 0xe001f), (0xe0080, 0xe00ff), (0xe01f0, 0xeffff),
+    (0xffffe, 0xfffff), (0x10fffe, 0x10ffff),
+];
+
 #[cfg(test)]
 mod tests {
-    use super::{count, format_field, get_pad, FileInfo};
-    use std::io::Cursor;
+    use super::{
+        count, digits, format_field, Charset, Counter, Decoding, FileInfo, FreqOptions, Mode,
+        Watched, WordMode,
+    };
+    use std::{
+        fs::{self, OpenOptions},
+        io::{Cursor, Write},
+    };
 
     #[test]
     fn test_count() {
         let text = "I don't want the world. I just want your half.\r\n";
-        let info = count(Cursor::new(text));
+        let info = count(Cursor::new(text), Decoding::plain(Charset::Utf8));
         assert!(info.is_ok()); // cursor create successfully
 
         let expected = FileInfo {
@@ -206,10 +1731,203 @@ mod tests {
             word_count: 10,
             char_count: 48,
             byte_count: 48,
+            max_line_length: 46,
+            ..FileInfo::new()
         };
         assert_eq!(info.unwrap(), expected);
     }
 
+    fn count_str(text: &[u8], charset: Charset) -> FileInfo {
+        count(Cursor::new(text), Decoding::plain(charset)).unwrap()
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        // invalid bytes are neither chars nor word breaks, but still bytes
+        let info = count_str(b"a\xffb c\n", Charset::Utf8);
+        let expected = FileInfo {
+            line_count: 1,
+            word_count: 2,
+            char_count: 5,
+            byte_count: 6,
+            max_line_length: 4,
+            ..FileInfo::new()
+        };
+        assert_eq!(info, expected);
+
+        // and on their own they don't make a word
+        assert_eq!(count_str(b"\xff \xff\n", Charset::Utf8).word_count, 0);
+        // a truncated sequence at the end is invalid as well
+        assert_eq!(count_str(b"\xc3\xa9\xc3", Charset::Utf8).char_count, 1);
+    }
+
+    #[test]
+    fn test_count_single_byte() {
+        // in the C locale non-ascii bytes are chars, but nothing printable
+        let info = count_str("é é\n".as_bytes(), Charset::SingleByte);
+        let expected = FileInfo {
+            line_count: 1,
+            word_count: 0,
+            char_count: 6,
+            byte_count: 6,
+            max_line_length: 1,
+            ..FileInfo::new()
+        };
+        assert_eq!(info, expected);
+    }
+
+    #[test]
+    fn test_count_lines_are_newlines() {
+        assert_eq!(count_str(b"one\ntwo", Charset::Utf8).line_count, 1);
+        assert_eq!(count_str(b"", Charset::Utf8).line_count, 0);
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let width = |text: &str| count_str(text.as_bytes(), Charset::Utf8).max_line_length;
+        assert_eq!(width(""), 0);
+        assert_eq!(width("hello\n"), 5);
+        assert_eq!(width("a\tb"), 9);
+        assert_eq!(width("\t\t"), 16);
+        assert_eq!(width("abcdefgh\t"), 16);
+        // wide chars take two columns, combining marks none
+        assert_eq!(width("日本語\n"), 6);
+        assert_eq!(width("e\u{301}"), 1);
+        // carriage return starts over, the widest part wins
+        assert_eq!(width("long line\rab\n"), 9);
+        assert_eq!(width("ab\rlong line"), 9);
+        // the widest line of the file
+        assert_eq!(width("ab\nlong line\nabc\n"), 9);
+    }
+
+    #[test]
+    fn test_word_freq() {
+        let text = b"The cat, the hat.\nA cat\xffs 'the'\n";
+        let freq = |ignore_case, strip_punct, min_len| {
+            let options = FreqOptions {
+                ignore_case,
+                strip_punct,
+                min_len,
+            };
+            let decoding = Decoding {
+                freq: Some(options),
+                ..Decoding::plain(Charset::Utf8)
+            };
+            count(Cursor::new(text), decoding).unwrap()
+        };
+
+        // the same words -w counts
+        let info = freq(false, false, 0);
+        assert_eq!(info.word_count, 7);
+        assert_eq!(info.word_freq.values().sum::<usize>(), 7);
+        assert_eq!(info.word_freq["cat,"], 1);
+        assert_eq!(info.word_freq["cats"], 1);
+        assert_eq!(info.word_freq["'the'"], 1);
+
+        let info = freq(true, true, 0);
+        assert_eq!(info.word_freq["the"], 3);
+        assert_eq!(info.word_freq["cat"], 1);
+        assert_eq!(info.word_freq["a"], 1);
+
+        let info = freq(true, true, 2);
+        assert!(!info.word_freq.contains_key("a"));
+        assert_eq!(info.word_freq.len(), 4);
+    }
+
+    #[test]
+    fn test_unicode_words_and_graphemes() {
+        let decoding = Decoding {
+            words: WordMode::Unicode,
+            graphemes: true,
+            ..Decoding::plain(Charset::Utf8)
+        };
+        let info = |text: &[u8]| count(Cursor::new(text), decoding).unwrap();
+
+        // punctuation isn't a word, and each ideograph is one
+        assert_eq!(info("Hello, world!\n".as_bytes()).word_count, 2);
+        assert_eq!(info("can't stop\n".as_bytes()).word_count, 2);
+        assert_eq!(info("日本語のテキスト\n".as_bytes()).word_count, 5);
+        // invalid bytes split words
+        assert_eq!(info(b"ab\xffcd\n").word_count, 2);
+
+        // a base and its combining mark, a flag and "\r\n" are one grapheme each
+        let text = "e\u{301}\u{1f1ef}\u{1f1f5}\r\n";
+        let counted = info(text.as_bytes());
+        assert_eq!(counted.grapheme_count, 3);
+        assert_eq!(counted.char_count, 6);
+        assert_eq!(info(b"a\xffb\n").grapheme_count, 3);
+    }
+
+    #[test]
+    fn test_counter_state_is_bounded() {
+        // a long line with no newline, cut so that chars get split between chunks
+        let text = "ab é\u{301}, 日本 can't ".repeat(10_000);
+        let unicode = Decoding {
+            words: WordMode::Unicode,
+            graphemes: true,
+            ..Decoding::plain(Charset::Utf8)
+        };
+        for decoding in [Decoding::plain(Charset::Utf8), unicode] {
+            let mut counter = Counter::with_decoding(decoding);
+            for chunk in text.as_bytes().chunks(7) {
+                counter.update(chunk);
+                assert!(counter.partial.len() <= 3);
+                assert!(counter.word.is_empty());
+                assert!(counter.word_tail.len() <= 16);
+                assert!(counter.grapheme_tail.len() <= 4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_watched_update() {
+        let path = std::env::temp_dir().join(format!("wcr-watch-{}", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let mode = Mode::Full(Decoding::plain(Charset::Utf8));
+        let append = |text: &str| {
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        };
+        fs::write(&path, "one two\nthr").unwrap();
+
+        // a line still being written counts its bytes and the words started so far
+        let mut watched = Watched::new(filename, mode);
+        watched.update().unwrap();
+        assert_eq!(watched.offset, 11);
+        assert_eq!(watched.file_info().byte_count, 11);
+        assert_eq!(watched.file_info().word_count, 3);
+
+        append("ee four\n");
+        watched.update().unwrap();
+        assert_eq!(watched.file_info().line_count, 2);
+        assert_eq!(watched.file_info().word_count, 4);
+        assert_eq!(watched.file_info().byte_count, 19);
+
+        // a shorter file starts the counts over
+        fs::write(&path, "five\n").unwrap();
+        watched.update().unwrap();
+        assert_eq!(watched.file_info().line_count, 1);
+        assert_eq!(watched.file_info().word_count, 1);
+        assert_eq!(watched.offset, 5);
+
+        // and so does one truncated and written past the offset again
+        fs::write(&path, "six seven eight\n").unwrap();
+        watched.update().unwrap();
+        assert_eq!(watched.file_info().word_count, 3);
+        assert_eq!(watched.file_info().byte_count, 16);
+
+        // or a new file in its place, even one that starts the same
+        let rotated = path.with_extension("1");
+        fs::rename(&path, &rotated).unwrap();
+        fs::write(&path, "six seven eight\nnine ten\n").unwrap();
+        watched.update().unwrap();
+        assert_eq!(watched.file_info().word_count, 5);
+        assert_eq!(watched.file_info().byte_count, 25);
+
+        fs::remove_file(&rotated).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn test_format_field() {
         assert_eq!(format_field(&1, 1, false), "");
@@ -218,11 +1936,11 @@ mod tests {
     }
 
     #[test]
-    fn test_get_pad() {
-        assert_eq!(get_pad(100), 4);
-        assert_eq!(get_pad(0), 2);
-        assert_eq!(get_pad(2), 2);
-        assert_eq!(get_pad(20), 3);
+    fn test_digits() {
+        assert_eq!(digits(100), 3);
+        assert_eq!(digits(0), 1);
+        assert_eq!(digits(2), 1);
+        assert_eq!(digits(20), 2);
     }
 }
 
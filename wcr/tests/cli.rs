@@ -1,3 +1,6 @@
+// the older tests borrow their argument arrays
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use std::{fs, error::Error};
 
 use assert_cmd::Command;
@@ -8,6 +11,7 @@ type TestResult = Result<(), Box<dyn Error>>;
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const CTRL: &str = "tests/inputs/ctrl.txt";
 
 const PRG: &str = "wcr";
 
@@ -48,7 +52,7 @@ fn run(args: &[&str], filename: &str) -> TestResult {
         .args(args)
         .assert()
         .success()
-        .stdout(predicates::str::is_match(expected)?);
+        .stdout(expected);
 
     Ok(())
 }
@@ -62,7 +66,7 @@ fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult
         .args(args)
         .assert()
         .success()
-        .stdout(predicates::str::is_match(expected)?);
+        .stdout(expected);
 
     Ok(())
 }
@@ -126,6 +130,11 @@ fn fox_bytes_lines() -> TestResult {
     run(&["-l", "-c", FOX], "tests/expected/fox.txt.cl.out")
 }
 
+#[test]
+fn fox_max_line_length() -> TestResult {
+    run(&["-L", FOX], "tests/expected/fox.txt.L.out")
+}
+
 #[test]
 fn atlamal() -> TestResult {
     run(&[ATLAMAL], "tests/expected/atlamal.txt.out")
@@ -161,11 +170,46 @@ fn atlamal_bytes_lines() -> TestResult {
     run(&["-l", "-c", ATLAMAL], "tests/expected/atlamal.txt.cl.out")
 }
 
+#[test]
+fn atlamal_max_line_length() -> TestResult {
+    run(&["--max-line-length", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+#[test]
+fn atlamal_lines_max_line_length() -> TestResult {
+    run(&["-l", "-L", ATLAMAL], "tests/expected/atlamal.txt.lL.out")
+}
+
 #[test]
 fn atlamal_stdin() -> TestResult {
     run_stdin(&[], ATLAMAL, "tests/expected/atlamal.txt.stdin.out")
 }
 
+#[test]
+fn atlamal_stdin_lines() -> TestResult {
+    run_stdin(&["-l"], ATLAMAL, "tests/expected/atlamal.txt.stdin.l.out")
+}
+
+#[test]
+fn atlamal_stdin_lines_max_line_length() -> TestResult {
+    run_stdin(&["-lL"], ATLAMAL, "tests/expected/atlamal.txt.stdin.lL.out")
+}
+
+#[test]
+fn atlamal_stdin_dash() -> TestResult {
+    run_stdin(&["-", FOX], ATLAMAL, "tests/expected/atlamal.txt.stdin.dash.out")
+}
+
+#[test]
+fn control_chars_dont_start_words() -> TestResult {
+    run(&["-w", CTRL], "tests/expected/ctrl.txt.w.out")
+}
+
+#[test]
+fn directory_gets_a_row() -> TestResult {
+    run(&["tests/inputs", FOX], "tests/expected/dir.out")
+}
+
 #[test]
 fn test_all() -> TestResult {
     run(&[EMPTY, FOX, ATLAMAL], "tests/expected/all.out")
@@ -200,3 +244,597 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+#[test]
+fn test_all_chars() -> TestResult {
+    run(&["-m", EMPTY, FOX, ATLAMAL], "tests/expected/all.m.out")
+}
+
+#[test]
+fn test_all_lines_words_chars() -> TestResult {
+    run(&["-lwm", EMPTY, FOX, ATLAMAL], "tests/expected/all.lwm.out")
+}
+
+#[test]
+fn test_all_words_chars() -> TestResult {
+    run(&["-wm", EMPTY, FOX, ATLAMAL], "tests/expected/all.wm.out")
+}
+
+#[test]
+fn test_all_chars_lines() -> TestResult {
+    run(&["-ml", EMPTY, FOX, ATLAMAL], "tests/expected/all.ml.out")
+}
+
+#[test]
+fn test_all_max_line_length() -> TestResult {
+    run(&["-L", EMPTY, FOX, ATLAMAL], "tests/expected/all.L.out")
+}
+
+#[test]
+fn test_all_lines_max_line_length() -> TestResult {
+    run(&["-lL", EMPTY, FOX, ATLAMAL], "tests/expected/all.lL.out")
+}
+
+#[test]
+fn test_all_counts_with_chars() -> TestResult {
+    run(&["-lwmL", EMPTY, FOX, ATLAMAL], "tests/expected/all.lwmL.out")
+}
+
+#[test]
+fn test_all_counts_with_bytes() -> TestResult {
+    run(&["-lwcL", EMPTY, FOX, ATLAMAL], "tests/expected/all.lwcL.out")
+}
+
+#[test]
+fn test_all_missing_file() -> TestResult {
+    // the total still gets printed, and the width comes from the files that are there
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["-l", FOX, &bad])
+        .assert()
+        .success()
+        .stdout(format!(" 1 {}\n 1 total\n", FOX));
+
+    Ok(())
+}
+
+#[test]
+fn files0_from_file_lines_up() -> TestResult {
+    // a list in a regular file is read up front, like GNU wc does, so the rows line up
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    let list = std::env::temp_dir().join(format!("wcr-files0-{}", get_random_string()));
+    fs::write(&list, format!("{}\0{}\0{}\0", EMPTY, FOX, ATLAMAL))?;
+    let out = Command::cargo_bin(PRG)?
+        .arg("--files0-from")
+        .arg(&list)
+        .output()?;
+    fs::remove_file(&list)?;
+
+    assert_eq!(String::from_utf8(out.stdout)?, expected);
+
+    Ok(())
+}
+
+fn run_bytes(args: &[&str], locale: &str, input: &[u8], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("LC_ALL", locale)
+        .write_stdin(input)
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(expected)?);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_utf8() -> TestResult {
+    run_bytes(&[], "C.UTF-8", b"a\xffb c\n", r"^\s*1\s+2\s+6\n$")
+}
+
+#[test]
+fn unprintable_chars_dont_start_words() -> TestResult {
+    // glibc takes U+0378 (unassigned), U+FFFE, U+FDD0 and U+2028 as unprintable, U+00AD as
+    // printable and U+0085 as no space, while GNU wc splits words at U+2060
+    let input = "\u{378} \u{fffe} \u{fdd0} \u{2028} \u{ad} a\u{85}b c\u{2060}d\n";
+    run_bytes(&["-w"], "C.UTF-8", input.as_bytes(), r"^\s*4\n$")
+}
+
+#[test]
+fn invalid_utf8_chars() -> TestResult {
+    run_bytes(&["-m"], "C.UTF-8", b"a\xffb c\n", r"^\s*5\n$")
+}
+
+#[test]
+fn beyond_unicode_chars() -> TestResult {
+    // glibc decodes U+110000 and U+4000000 as unprintable chars, and U+2028 takes no width
+    let input = b"a\xf4\x90\x80\x80b\xe2\x80\xa8\xfc\x84\x80\x80\x80\x80\n";
+    run_bytes(&["-mL"], "C.UTF-8", input, r"^\s*6\s+2\n$")
+}
+
+#[test]
+fn invalid_utf8_chars_c_locale() -> TestResult {
+    run_bytes(&["-m"], "C", b"a\xffb c\n", r"^\s*6\n$")
+}
+
+#[test]
+fn no_locale_is_c() -> TestResult {
+    // without any locale set, it's POSIX "C", where every byte is a character
+    Command::cargo_bin(PRG)?
+        .env_remove("LC_ALL")
+        .env_remove("LC_CTYPE")
+        .env_remove("LANG")
+        .write_stdin("é\n")
+        .arg("-m")
+        .assert()
+        .success()
+        .stdout("3\n");
+
+    Ok(())
+}
+
+fn words_of(output: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(output)
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let expected = Command::cargo_bin(PRG)?
+        .args([EMPTY, FOX, ATLAMAL])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let list = format!("{}\0{}\0{}\0", EMPTY, FOX, ATLAMAL);
+    let out = Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-"])
+        .write_stdin(list)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    // same counts and names, only the padding differs
+    assert_eq!(words_of(&out), words_of(&expected));
+
+    Ok(())
+}
+
+#[test]
+fn files0_from_bad_entries() -> TestResult {
+    let bad = gen_bad_file();
+    let list = format!("{}\0\0{}\0-\0", FOX, bad);
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-", "-l"])
+        .write_stdin(list)
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"^1 tests/inputs/fox.txt\n1 total\n$")?)
+        .stderr(predicates::str::contains("-:2: invalid zero-length file name"))
+        .stderr(predicates::str::contains(format!("{}: No such file or directory", bad)))
+        .stderr(predicates::str::contains("no file name of '-' allowed"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_files0_from_and_files() -> TestResult {
+    let expected = "the argument '--files0-from <F>' cannot be used with '[FILES]...'";
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", "-", FOX])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(expected));
+
+    Ok(())
+}
+
+fn run_format(args: &[&str], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn format_json() -> TestResult {
+    let expected = concat!(
+        r#"{"type":"file","path":"tests/inputs/fox.txt","lines":1,"words":9,"word_mode":"gnu","chars":48,"graphemes":null,"bytes":48,"max_line_length":50,"error":null}"#,
+        "\n",
+        r#"{"type":"file","path":"tests/inputs/empty.txt","lines":0,"words":0,"word_mode":"gnu","chars":0,"graphemes":null,"bytes":0,"max_line_length":0,"error":null}"#,
+        "\n",
+        r#"{"type":"total","path":null,"lines":1,"words":9,"word_mode":"gnu","chars":48,"graphemes":null,"bytes":48,"max_line_length":50,"error":null}"#,
+        "\n",
+    );
+    run_format(&["--format", "json", FOX, EMPTY], expected)
+}
+
+#[test]
+fn format_csv() -> TestResult {
+    let expected = "type,path,lines,words,word_mode,chars,graphemes,bytes,max_line_length,error\n\
+                    file,tests/inputs/fox.txt,1,9,gnu,48,,48,50,\n\
+                    total,,1,9,gnu,48,,48,50,\n";
+    run_format(&["--format", "csv", FOX], expected)
+}
+
+#[test]
+fn format_tsv() -> TestResult {
+    let expected = "type\tpath\tlines\twords\tword_mode\tchars\tgraphemes\tbytes\tmax_line_length\terror\n\
+                    file\ttests/inputs/empty.txt\t0\t0\tgnu\t0\t\t0\t0\t\n\
+                    total\t\t0\t0\tgnu\t0\t\t0\t0\t\n";
+    run_format(&["--format", "tsv", EMPTY], expected)
+}
+
+#[test]
+fn format_graphemes_and_word_mode() -> TestResult {
+    let expected = concat!(
+        r#"{"type":"file","path":"tests/inputs/fox.txt","lines":1,"words":9,"word_mode":"unicode","chars":48,"graphemes":48,"bytes":48,"max_line_length":50,"error":null}"#,
+        "\n",
+        r#"{"type":"total","path":null,"lines":1,"words":9,"word_mode":"unicode","chars":48,"graphemes":48,"bytes":48,"max_line_length":50,"error":null}"#,
+        "\n",
+    );
+    run_format(&["--graphemes", "--words=unicode", "--format", "json", FOX], expected)
+}
+
+#[test]
+fn format_error_record() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!(
+        "type,path,lines,words,word_mode,chars,graphemes,bytes,max_line_length,error\n\
+         error,{},,,,,,,,No such file or directory (os error 2)\n\
+         total,,0,0,gnu,0,,0,0,\n",
+        bad
+    );
+    Command::cargo_bin(PRG)?
+        .args(["--format", "csv", &bad])
+        .assert()
+        .stdout(expected)
+        .stderr(predicates::str::contains(format!("{}: No such file or directory", bad)));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "xml"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid value 'xml'"));
+
+    Ok(())
+}
+
+fn run_jobs(args: &[&str], stdin: &str) -> TestResult {
+    let serial = Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(stdin)
+        .output()?;
+    let parallel = Command::cargo_bin(PRG)?
+        .args(["-j", "4"])
+        .args(args)
+        .write_stdin(stdin)
+        .output()?;
+
+    assert_eq!(
+        String::from_utf8_lossy(&parallel.stdout),
+        String::from_utf8_lossy(&serial.stdout)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&parallel.stderr),
+        String::from_utf8_lossy(&serial.stderr)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn jobs_match_serial() -> TestResult {
+    let bad = gen_bad_file();
+    let mut files = vec![];
+    for _ in 0..20 {
+        files.extend([ATLAMAL, EMPTY, FOX]);
+    }
+    files.insert(7, &bad);
+
+    run_jobs(&files, "")?;
+    run_jobs(&[&["-lmL"], &files[..]].concat(), "")?;
+    run_jobs(&[&["--format", "json"], &files[..]].concat(), "")
+}
+
+#[test]
+fn jobs_match_serial_stdin() -> TestResult {
+    // only the first "-" gets stdin, whichever thread would have been faster
+    let input = fs::read_to_string(ATLAMAL)?;
+    run_jobs(&[FOX, "-", ATLAMAL, "-"], &input)
+}
+
+#[test]
+fn jobs_match_serial_files0_from() -> TestResult {
+    let mut list = String::new();
+    for _ in 0..1500 {
+        for file in [ATLAMAL, EMPTY, FOX] {
+            list.push_str(file);
+            list.push('\0');
+        }
+    }
+    run_jobs(&["--files0-from", "-"], &list)
+}
+
+#[test]
+fn fast_path_matches_full_count() -> TestResult {
+    // -l and -c take the fast path, adding -w makes every line get decoded
+    let fast = Command::cargo_bin(PRG)?
+        .args(["-lc", EMPTY, FOX, ATLAMAL])
+        .output()?;
+    let full = Command::cargo_bin(PRG)?
+        .args(["-lwc", EMPTY, FOX, ATLAMAL])
+        .output()?;
+
+    let drop_words = |out: &[u8]| -> Vec<Vec<String>> {
+        String::from_utf8_lossy(out)
+            .lines()
+            .map(|line| {
+                let mut fields = line.split_whitespace().map(String::from).collect::<Vec<_>>();
+                fields.remove(1);
+                fields
+            })
+            .collect()
+    };
+    let fast = String::from_utf8_lossy(&fast.stdout)
+        .lines()
+        .map(|line| line.split_whitespace().map(String::from).collect())
+        .collect::<Vec<Vec<String>>>();
+    assert_eq!(fast, drop_words(&full.stdout));
+
+    Ok(())
+}
+
+#[test]
+fn lines_without_final_newline() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l"])
+        .write_stdin("one\ntwo")
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"^\s*1\n$")?);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn bytes_of_proc_file() -> TestResult {
+    // /proc files report a size of 0, so they have to be read
+    Command::cargo_bin(PRG)?
+        .args(["-c", "/proc/self/cmdline"])
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"^\s*[1-9][0-9]* /proc/self/cmdline\n$")?);
+
+    Ok(())
+}
+
+#[test]
+fn recursive_matches_listed_files() -> TestResult {
+    // the walk goes in name order
+    let listed = Command::cargo_bin(PRG)?
+        .args([ATLAMAL, CTRL, EMPTY, FOX])
+        .output()?;
+    Command::cargo_bin(PRG)?
+        .args(["-r", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(String::from_utf8(listed.stdout)?);
+
+    Ok(())
+}
+
+#[test]
+fn recursive_include_exclude() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "-l", "--include", "*o*.txt", "--exclude", "empty*", "tests"])
+        .assert()
+        .success()
+        .stdout("1 tests/inputs/fox.txt\n");
+
+    Ok(())
+}
+
+#[test]
+fn recursive_exclude_dir() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "-l", "--exclude", "inputs", "--include", "*.txt", "tests"])
+        .assert()
+        .success()
+        .stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn by_ext() -> TestResult {
+    // the character counts below are those of a UTF-8 locale
+    Command::cargo_bin(PRG)?
+        .env("LC_ALL", "C.UTF-8")
+        .args(["--by-ext", "--format", "csv", FOX, ATLAMAL, "tests/cli.rs", "mk-outs.sh"])
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(
+            r"^type,path,lines,words,word_mode,chars,graphemes,bytes,max_line_length,error
+extension,.rs,[0-9]+,[0-9]+,gnu,[0-9]+,,[0-9,]+,
+extension,.sh,[0-9]+,[0-9]+,gnu,[0-9]+,,[0-9,]+,
+extension,.txt,5,38,gnu,207,,225,50,
+total,,[0-9]+,[0-9]+,gnu,[0-9]+,,[0-9,]+,
+$",
+        )?);
+
+    Ok(())
+}
+
+#[test]
+fn dies_include_without_recursive() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--include", "*.txt"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--recursive"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_glob() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "--include", "[", "tests"])
+        .assert()
+        .stderr(predicates::str::contains("unclosed character class"));
+
+    Ok(())
+}
+
+#[test]
+fn freq_matches_word_count() -> TestResult {
+    let out = Command::cargo_bin(PRG)?
+        .args(["--freq=0", EMPTY, FOX, ATLAMAL])
+        .output()?;
+    let freq_total = String::from_utf8(out.stdout)?
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap().parse::<usize>())
+        .sum::<Result<usize, _>>()?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-w", EMPTY, FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(format!("{} total", freq_total)));
+
+    Ok(())
+}
+
+#[test]
+fn freq_top() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--freq=3", FOX])
+        .assert()
+        .success()
+        .stdout(" 1 The\n 1 brown\n 1 dog.\n");
+
+    Ok(())
+}
+
+#[test]
+fn freq_options() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--freq", "--ignore-case", "--strip-punct", "--min-len", "4", FOX])
+        .assert()
+        .success()
+        .stdout(" 1 brown\n 1 jumps\n 1 lazy\n 1 over\n 1 quick\n");
+
+    Ok(())
+}
+
+#[test]
+fn dies_freq_options_without_freq() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--ignore-case", FOX])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--freq"));
+
+    Ok(())
+}
+
+#[test]
+fn unicode_words() -> TestResult {
+    // punctuation isn't a word, each ideograph is one, and -w stays GNU's
+    let input = "Hello, world! 日本語\n";
+    run_bytes(&["--words=unicode"], "C.UTF-8", input.as_bytes(), r"^5\n$")?;
+    run_bytes(&["-w"], "C.UTF-8", input.as_bytes(), r"^3\n$")
+}
+
+#[test]
+fn unicode_words_freq() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--freq", "--words=unicode", "--ignore-case"])
+        .write_stdin("The cat. THE hat!\n")
+        .assert()
+        .success()
+        .stdout(" 2 the\n 1 cat\n 1 hat\n");
+
+    Ok(())
+}
+
+#[test]
+fn graphemes() -> TestResult {
+    // e and a combining accent are two chars but one grapheme
+    let input = "e\u{301}te\u{301}\n";
+    run_bytes(&["-m", "--graphemes"], "C.UTF-8", input.as_bytes(), r"^\s+6\s+4\n$")
+}
+
+#[test]
+fn dies_bad_word_mode() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--words=latin", FOX])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid value 'latin'"));
+
+    Ok(())
+}
+
+#[test]
+fn watch_passes() -> TestResult {
+    // nothing changes between the passes, so the rates are all zero after the first
+    let expected = format!(
+        "      1       9      48          - {FOX}\n\n      1       9      48      0.0/s {FOX}\n\n"
+    );
+    Command::cargo_bin(PRG)?
+        .args(["--watch", "0.05", "--passes", "2", FOX])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Ok(())
+}
+
+#[test]
+fn dies_watch_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--watch", "1"])
+        .assert()
+        .stderr("--watch needs files to read, not stdin\n");
+
+    Ok(())
+}
+
+#[test]
+fn dies_bad_watch_interval() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--watch", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("isn't a positive number of seconds"));
+
+    Ok(())
+}
+
+#[test]
+fn dies_watch_recursive_or_jobs() -> TestResult {
+    for flag in ["-r", "-j2"] {
+        Command::cargo_bin(PRG)?
+            .args(["--watch", "1", flag, "tests/inputs"])
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("cannot be used with"));
+    }
+
+    Ok(())
+}
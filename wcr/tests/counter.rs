@@ -0,0 +1,106 @@
+use proptest::prelude::*;
+use wcr::{Charset, Counter, Counts, WordMode};
+
+/// bytes that make the boundaries interesting: whitespace, newlines, tabs, multi-byte and
+/// combining chars, bytes that aren't valid UTF-8 on their own, and the longer sequences
+/// glibc decodes above U+10FFFF
+fn input() -> impl Strategy<Value = Vec<u8>> {
+    let piece = prop_oneof![
+        Just(b" ".to_vec()),
+        Just(b"\n".to_vec()),
+        Just(b"\r\n".to_vec()),
+        Just(b"\t".to_vec()),
+        Just("é".as_bytes().to_vec()),
+        Just("e\u{301}".as_bytes().to_vec()),
+        Just("日本".as_bytes().to_vec()),
+        Just("\u{1f1ef}\u{1f1f5}".as_bytes().to_vec()),
+        Just(b"can't,".to_vec()),
+        Just(b"\xff".to_vec()),
+        Just(b"\xc3".to_vec()),
+        Just(b"\xf4\x90\x80\x80".to_vec()),
+        Just(b"\xfc\x84\x80".to_vec()),
+        "[a-z]{1,5}".prop_map(String::into_bytes),
+        any::<u8>().prop_map(|b| vec![b]),
+    ];
+    prop::collection::vec(piece, 0..40).prop_map(|pieces| pieces.concat())
+}
+
+fn count_whole(counter: Counter, input: &[u8]) -> Counts {
+    let mut counter = counter;
+    counter.update(input);
+    counter.finish()
+}
+
+/// feeds `input` cut at `cuts`, which are taken modulo its length
+fn count_chunked(counter: Counter, input: &[u8], cuts: &[usize]) -> Counts {
+    let mut cuts = cuts
+        .iter()
+        .map(|cut| cut % (input.len() + 1))
+        .collect::<Vec<_>>();
+    cuts.push(0);
+    cuts.push(input.len());
+    cuts.sort_unstable();
+
+    let mut counter = counter;
+    for bounds in cuts.windows(2) {
+        counter.update(&input[bounds[0]..bounds[1]]);
+    }
+    counter.finish()
+}
+
+fn counters() -> [fn() -> Counter; 3] {
+    [
+        Counter::new,
+        || Counter::new().charset(Charset::SingleByte),
+        || Counter::new().words(WordMode::Unicode).graphemes(true),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn chunked_matches_whole(input in input(), cuts in prop::collection::vec(any::<usize>(), 0..10)) {
+        for counter in counters() {
+            prop_assert_eq!(
+                count_chunked(counter(), &input, &cuts),
+                count_whole(counter(), &input)
+            );
+        }
+    }
+
+    #[test]
+    fn byte_at_a_time_matches_whole(input in input()) {
+        let cuts = (0..input.len()).collect::<Vec<_>>();
+        for counter in counters() {
+            prop_assert_eq!(
+                count_chunked(counter(), &input, &cuts),
+                count_whole(counter(), &input)
+            );
+        }
+    }
+
+    #[test]
+    fn lines_and_bytes(input in input()) {
+        let counts = count_whole(Counter::new(), &input);
+        prop_assert_eq!(counts.bytes, input.len());
+        prop_assert_eq!(counts.lines, input.iter().filter(|&&b| b'\n' == b).count());
+        prop_assert!(counts.chars <= counts.bytes);
+    }
+}
+
+#[test]
+fn counts_like_gnu_wc() {
+    // what `wc -lwmcL` prints for it in a UTF-8 locale
+    let counts = count_whole(
+        Counter::new(),
+        "The quick brown fox\tjumps\r\nété\n".as_bytes(),
+    );
+    let expected = Counts {
+        lines: 2,
+        words: 6,
+        chars: 31,
+        graphemes: 0,
+        bytes: 33,
+        max_line_length: 29,
+    };
+    assert_eq!(counts, expected);
+}